@@ -1,5 +1,5 @@
 use anyhow::Result;
-use util::Input;
+use util::{interval::IntervalSet, Input};
 
 fn parse_pair(pair: &str) -> (u32, u32) {
     let (fst, snd) = pair.split_once('-').unwrap();
@@ -22,7 +22,9 @@ fn main() -> Result<()> {
         if !line.is_empty() {
             let ((a, b), (x, y)) = parse_pairs(&line);
 
-            if (a <= x && b >= y) || (x <= a && y >= b) {
+            if IntervalSet::from((a, b)).contains_interval(x, y)
+                || IntervalSet::from((x, y)).contains_interval(a, b)
+            {
                 count += 1;
             }
         }
@@ -1,90 +1,37 @@
-use std::collections::HashSet;
+use std::env;
 
-use anyhow::{anyhow, bail, Result};
-use util::Input;
-
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Right,
-    Left,
-    Up,
-    Down,
-}
-
-fn parse_move(line: String) -> Result<(Direction, usize)> {
-    let (dir, num) = line
-        .split_once(' ')
-        .ok_or_else(|| anyhow!("couldn't parse direction"))?;
-
-    let dir = match dir {
-        "R" => Direction::Right,
-        "L" => Direction::Left,
-        "U" => Direction::Up,
-        "D" => Direction::Down,
-        _ => bail!("unknown direction"),
-    };
-
-    Ok((dir, num.parse()?))
-}
-
-struct State {
-    head: (isize, isize),
-    tail: (isize, isize),
-    visited: HashSet<(isize, isize)>,
-}
-
-impl State {
-    fn new() -> Self {
-        Self {
-            head: (0, 0),
-            tail: (0, 0),
-            visited: HashSet::from([(0, 0)]),
-        }
-    }
-
-    fn do_move(&mut self, dir: Direction) {
-        match dir {
-            Direction::Right => self.head.0 += 1,
-            Direction::Left => self.head.0 -= 1,
-            Direction::Up => self.head.1 -= 1,
-            Direction::Down => self.head.1 += 1,
-        }
-        let delta = (self.head.0 - self.tail.0, self.head.1 - self.tail.1);
-        // Touching
-        if delta.0.abs() <= 1 && delta.1.abs() <= 1 {
-            return;
-        }
-        // Same column, vertical move necessary
-        if delta.0.abs() == 2 && delta.1 == 0 {
-            self.tail.0 += delta.0 / delta.0.abs();
-        }
-        // Same row, horizontal move necessary
-        else if delta.1.abs() == 2 && delta.0 == 0 {
-            self.tail.1 += delta.1 / delta.1.abs();
-        }
-        // Diagonal move necessary
-        else {
-            self.tail.0 += delta.0 / delta.0.abs();
-            self.tail.1 += delta.1 / delta.1.abs();
-        }
-        self.visited.insert(self.tail);
-    }
-}
+use anyhow::Result;
+use util::{
+    rope::{self, Rope, Slack},
+    Input,
+};
 
 fn main() -> Result<()> {
-    let input = Input::new()
-        .into_lines()?
-        .into_iter()
-        .map(parse_move)
-        .collect::<Result<Vec<_>>>()?;
-    let mut state = State::new();
-    for (dir, count) in input {
-        for _ in 0..count {
-            state.do_move(dir);
+    let motions = rope::parse_motions(&Input::new().into_lines()?)?;
+    let mut rope = Rope::new(2, Slack(1))?;
+
+    if env::args().any(|arg| arg == "--draw") {
+        let window = rope::head_bounds(&motions);
+        for motion in &motions {
+            rope.apply(motion);
+            println!("== {motion} ==");
+            println!();
+            for line in rope.snapshot().render(window) {
+                println!("{line}");
+            }
+            println!();
+        }
+        if let Some(visited) = rope.visited_grid(rope.len() - 1) {
+            for line in visited.render(window) {
+                println!("{line}");
+            }
+            println!();
         }
+    } else {
+        rope.run(&motions);
     }
 
-    println!("Tail visisted {} locations.", state.visited.len());
+    println!("Tail visisted {} locations.", rope.tail_visited().len());
 
     Ok(())
 }
@@ -1,41 +1,8 @@
 use anyhow::{bail, Result};
 use petgraph::{algo::dijkstra, stable_graph::NodeIndex, Graph};
-use util::Input;
+use util::{geom::Point2, Input};
 
-#[derive(Debug, Clone, Copy)]
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-impl Point {
-    fn left(&self) -> Point {
-        Point {
-            x: self.x - 1,
-            y: self.y,
-        }
-    }
-    fn right(&self) -> Point {
-        Point {
-            x: self.x + 1,
-            y: self.y,
-        }
-    }
-    fn up(&self) -> Point {
-        Point {
-            x: self.x,
-            y: self.y - 1,
-        }
-    }
-    fn down(&self) -> Point {
-        Point {
-            x: self.x,
-            y: self.y + 1,
-        }
-    }
-}
-
-fn cell_height(grid: &[Vec<char>], point: &Point) -> Result<u8> {
+fn cell_height(grid: &[Vec<char>], point: &Point2) -> Result<u8> {
     Ok(match grid[point.y as usize][point.x as usize] {
         'S' => 0,
         'E' => 25,
@@ -44,20 +11,20 @@ fn cell_height(grid: &[Vec<char>], point: &Point) -> Result<u8> {
     })
 }
 
-fn in_bounds(grid: &Vec<Vec<char>>, point: &Point) -> bool {
+fn in_bounds(grid: &Vec<Vec<char>>, point: &Point2) -> bool {
     !(point.x as usize >= grid[0].len() || point.y as usize >= grid.len())
 }
 
-fn get_index(indicies: &[Vec<NodeIndex>], point: &Point) -> NodeIndex {
+fn get_index(indicies: &[Vec<NodeIndex>], point: &Point2) -> NodeIndex {
     indicies[point.y as usize][point.x as usize]
 }
 
 fn try_link(
-    graph: &mut Graph<Point, ()>,
+    graph: &mut Graph<Point2, ()>,
     grid: &Vec<Vec<char>>,
     indicies: &[Vec<NodeIndex>],
-    from_point: &Point,
-    to_point: &Point,
+    from_point: &Point2,
+    to_point: &Point2,
 ) -> Result<()> {
     let height = cell_height(grid, from_point)?;
 
@@ -77,7 +44,7 @@ fn main() -> Result<()> {
         .map(|line| line.chars().collect::<Vec<_>>())
         .collect::<Vec<_>>();
 
-    let mut g = Graph::<Point, ()>::new();
+    let mut g = Graph::<Point2, ()>::new();
 
     let mut indicies = vec![vec![]; grid.len()];
 
@@ -86,10 +53,7 @@ fn main() -> Result<()> {
 
     for x in 0..grid[0].len() {
         for y in 0..grid.len() {
-            let pt = Point {
-                x: x as isize,
-                y: y as isize,
-            };
+            let pt = Point2::new(x as isize, y as isize);
             if grid[y][x] == 'S' {
                 start = Some(pt);
             }
@@ -104,10 +68,9 @@ fn main() -> Result<()> {
     for x in 0..indicies[0].len() {
         for y in 0..indicies.len() {
             let point = g[indicies[y][x]];
-            try_link(&mut g, &grid, &indicies, &point, &point.left())?;
-            try_link(&mut g, &grid, &indicies, &point, &point.right())?;
-            try_link(&mut g, &grid, &indicies, &point, &point.up())?;
-            try_link(&mut g, &grid, &indicies, &point, &point.down())?;
+            for neighbor in point.neighbors() {
+                try_link(&mut g, &grid, &indicies, &point, &neighbor)?;
+            }
         }
     }
 
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use util::{linked_grid::LinkedGrid, Input};
+use util::{geom::Point2, linked_grid::LinkedGrid, Input};
 
 mod parser {
     use anyhow::{bail, Result};
@@ -81,7 +81,7 @@ fn main() -> Result<()> {
         }
     }
 
-    let mut sand = (500, 0);
+    let mut sand = Point2::new(500, 0);
 
     let mut resting = 0;
 
@@ -91,28 +91,28 @@ fn main() -> Result<()> {
         neighbors.sort_by_key(|p| p.x);
 
         if neighbors.is_empty() {
-            println!("{sand:?} has no neighbors");
+            println!("{sand} has no neighbors");
         }
 
         if neighbors[1].data == Contents::Empty {
-            sand = (neighbors[1].x, neighbors[1].y);
+            sand = neighbors[1].position();
             continue;
         }
         if neighbors[0].data == Contents::Empty {
-            sand = (neighbors[0].x, neighbors[0].y);
+            sand = neighbors[0].position();
             continue;
         }
         if neighbors[2].data == Contents::Empty {
-            sand = (neighbors[2].x, neighbors[2].y);
+            sand = neighbors[2].position();
             continue;
         }
-        lg.set_data(sand.0, sand.1, Contents::Sand)?;
+        lg.set_data(sand, Contents::Sand)?;
         resting += 1;
-        if sand == (500, 0) {
+        if sand == Point2::new(500, 0) {
             println!("Rested at output");
             break;
         }
-        sand = (500, 0);
+        sand = Point2::new(500, 0);
     }
 
     lg.draw_range((493, 0), 11, 12, |c| match c {
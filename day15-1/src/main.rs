@@ -1,6 +1,7 @@
 use anyhow::Result;
 use util::{
     grid::{Grid, SparseGrid},
+    interval::IntervalSet,
     Input,
 };
 
@@ -14,6 +15,7 @@ mod parser {
         combinator::map_res,
         IResult,
     };
+    use util::geom::Point2;
 
     fn from_dec(i: &str) -> Result<isize, ParseIntError> {
         i.parse()
@@ -26,17 +28,16 @@ mod parser {
         )(i)
     }
 
-    fn xy(i: &str) -> IResult<&str, (isize, isize)> {
+    fn xy(i: &str) -> IResult<&str, Point2> {
         let (i, _) = tag("x=")(i)?;
         let (i, x) = number(i)?;
         let (i, _) = tag(", y=")(i)?;
         let (i, y) = number(i)?;
 
-        Ok((i, (x, y)))
+        Ok((i, Point2::new(x, y)))
     }
 
-    #[allow(clippy::type_complexity)]
-    fn reading(i: &str) -> IResult<&str, ((isize, isize), (isize, isize))> {
+    fn reading(i: &str) -> IResult<&str, (Point2, Point2)> {
         let (i, _) = tag("Sensor at ")(i)?;
         let (i, sensor) = xy(i)?;
         let (i, _) = tag(": closest beacon is at ")(i)?;
@@ -45,7 +46,7 @@ mod parser {
         Ok((i, (sensor, beacon)))
     }
 
-    pub fn parse_reading(i: &str) -> Result<((isize, isize), (isize, isize))> {
+    pub fn parse_reading(i: &str) -> Result<(Point2, Point2)> {
         match reading(i) {
             Ok((rest, reading)) => {
                 if !rest.is_empty() {
@@ -66,11 +67,6 @@ enum Entity {
     Empty,
     Sensor,
     Beacon,
-    Covered,
-}
-
-fn manhattan(from: (isize, isize), to: (isize, isize)) -> isize {
-    (from.0 - to.0).abs() + (from.1 - to.1).abs()
 }
 
 fn main() -> Result<()> {
@@ -84,22 +80,21 @@ fn main() -> Result<()> {
         Entity::Empty => '.',
         Entity::Sensor => 'S',
         Entity::Beacon => 'B',
-        Entity::Covered => '#',
     }));
 
     let line_to_count = 2000000;
 
+    let mut coverage = IntervalSet::new();
+    let mut beacons_on_line = IntervalSet::new();
+
     for (sensor, beacon) in input {
-        let dist = manhattan(sensor, beacon);
-        let y_range = (sensor.1 - dist)..=(sensor.1 + dist);
-        if y_range.contains(&line_to_count) {
-            for x in (sensor.0 - dist)..=(sensor.0 + dist) {
-                if manhattan(sensor, (x, line_to_count)) <= dist
-                    && grid.get((x, line_to_count)) != Some(&Entity::Beacon)
-                {
-                    grid.set((x, line_to_count), Entity::Covered);
-                }
-            }
+        let dist = sensor.manhattan(beacon);
+        let reach = dist - (sensor.y - line_to_count).abs();
+        if reach >= 0 {
+            coverage.insert(sensor.x - reach, sensor.x + reach);
+        }
+        if beacon.y == line_to_count {
+            beacons_on_line.insert(beacon.x, beacon.x);
         }
         grid.set(sensor, Entity::Sensor);
         grid.set(beacon, Entity::Beacon);
@@ -107,12 +102,8 @@ fn main() -> Result<()> {
 
     println!("Bounds: {:?}", grid.get_bounds());
 
-    let ((min_x, _), (max_x, _)) = grid.get_bounds();
-
-    let covered = (min_x..=max_x)
-        .into_iter()
-        .filter(|x| grid.get((*x, line_to_count)) == Some(&Entity::Covered))
-        .count();
+    coverage.subtract_set(&beacons_on_line);
+    let covered = coverage.total_length();
 
     println!("Covered: {covered}");
 
@@ -1,46 +1,43 @@
-use std::collections::HashSet;
-
 use anyhow::Result;
-use geo::{line_intersection::line_intersection, Coord, Line};
 use util::{
+    geom::{find_uncovered, Diamond, Point2},
     grid::{Grid, SparseGrid},
     Input,
 };
 
 mod parser {
-    use std::num::ParseFloatError;
+    use std::num::ParseIntError;
 
     use anyhow::{bail, Result};
-    use geo::Coord;
     use nom::{
         bytes::complete::{tag, take_while1},
         character::is_digit,
         combinator::map_res,
         IResult,
     };
+    use util::geom::Point2;
 
-    fn from_dec(i: &str) -> Result<f64, ParseFloatError> {
+    fn from_dec(i: &str) -> Result<isize, ParseIntError> {
         i.parse()
     }
 
-    fn number(i: &str) -> IResult<&str, f64> {
+    fn number(i: &str) -> IResult<&str, isize> {
         map_res(
             take_while1(|c: char| is_digit(c as u8) || c == '-'),
             from_dec,
         )(i)
     }
 
-    fn xy(i: &str) -> IResult<&str, Coord<f64>> {
+    fn xy(i: &str) -> IResult<&str, Point2> {
         let (i, _) = tag("x=")(i)?;
         let (i, x) = number(i)?;
         let (i, _) = tag(", y=")(i)?;
         let (i, y) = number(i)?;
 
-        Ok((i, (x, y).into()))
+        Ok((i, Point2::new(x, y)))
     }
 
-    #[allow(clippy::type_complexity)]
-    fn reading(i: &str) -> IResult<&str, (Coord<f64>, Coord<f64>)> {
+    fn reading(i: &str) -> IResult<&str, (Point2, Point2)> {
         let (i, _) = tag("Sensor at ")(i)?;
         let (i, sensor) = xy(i)?;
         let (i, _) = tag(": closest beacon is at ")(i)?;
@@ -49,7 +46,7 @@ mod parser {
         Ok((i, (sensor, beacon)))
     }
 
-    pub fn parse_reading(i: &str) -> Result<(Coord<f64>, Coord<f64>)> {
+    pub fn parse_reading(i: &str) -> Result<(Point2, Point2)> {
         match reading(i) {
             Ok((rest, reading)) => {
                 if !rest.is_empty() {
@@ -72,10 +69,6 @@ enum Entity {
     Beacon,
 }
 
-fn manhattan(from: Coord<f64>, to: Coord<f64>) -> f64 {
-    (from.x - to.x).abs() + (from.y - to.y).abs()
-}
-
 fn main() -> Result<()> {
     let input = Input::new()
         .into_lines()?
@@ -90,79 +83,22 @@ fn main() -> Result<()> {
     }));
 
     for (sensor, beacon) in input.iter() {
-        grid.set((sensor.x as isize, sensor.y as isize), Entity::Sensor);
-        grid.set((beacon.x as isize, beacon.y as isize), Entity::Beacon);
+        grid.set(*sensor, Entity::Sensor);
+        grid.set(*beacon, Entity::Beacon);
     }
 
-    let lines = input
+    let diamonds = input
         .iter()
-        .map(|(sensor, beacon)| (sensor, manhattan(*sensor, *beacon)))
-        .flat_map(|(sensor, distance)| {
-            vec![
-                Line::new(
-                    (sensor.x + distance, sensor.y),
-                    (sensor.x, sensor.y + distance),
-                ),
-                Line::new(
-                    (sensor.x, sensor.y + distance),
-                    (sensor.x - distance, sensor.y),
-                ),
-                Line::new(
-                    (sensor.x - distance, sensor.y),
-                    (sensor.x, sensor.y - distance),
-                ),
-                Line::new(
-                    (sensor.x, sensor.y - distance),
-                    (sensor.x + distance, sensor.y),
-                ),
-            ]
-        })
+        .map(|(sensor, beacon)| Diamond::through(*sensor, *beacon))
         .collect::<Vec<_>>();
 
-    let mut intersections = HashSet::<Coord<isize>>::new();
-
-    for line in lines.iter() {
-        for other in lines.iter() {
-            if line == other {
-                continue;
-            }
-            match line_intersection(*line, *other) {
-                Some(geo::LineIntersection::SinglePoint { intersection, .. }) => {
-                    intersections.insert((intersection.x as isize, intersection.y as isize).into());
-                }
-                Some(geo::LineIntersection::Collinear { intersection }) => {
-                    let start = intersection.start;
-                    let end = intersection.end;
-                    intersections.insert((start.x as isize, start.y as isize).into());
-                    intersections.insert((end.x as isize, end.y as isize).into());
-                }
-                None => {}
-            };
-        }
-    }
-
     let max = 4_000_000;
 
-    let mut intersections = intersections.into_iter().collect::<Vec<_>>();
-    intersections.sort_by_key(|i| i.x);
-
-    'i: for intersection in intersections {
-        for x in (intersection.x - 1).max(0)..=(intersection.x + 1).min(max) {
-            'y: for y in (intersection.y - 1).max(0)..=(intersection.y + 1).min(max) {
-                for (sensor, distance) in input
-                    .iter()
-                    .map(|(sensor, beacon)| (sensor, manhattan(*sensor, *beacon)))
-                {
-                    let dist_to = manhattan(*sensor, (x as f64, y as f64).into());
-                    if dist_to <= distance {
-                        continue 'y;
-                    }
-                }
-                println!("Found {x}, {y}");
-                println!("Tuning freq: {}", (x * 4_000_000) + y);
-                break 'i;
-            }
-        }
+    if let Some(Point2 { x, y }) =
+        find_uncovered(&diamonds, (Point2::ORIGIN, Point2::new(max, max)))
+    {
+        println!("Found {x}, {y}");
+        println!("Tuning freq: {}", (x * 4_000_000) + y);
     }
 
     /*
@@ -0,0 +1,93 @@
+// Times sorting a large generated packet set three ways: the old comparison
+// that wrapped numbers in freshly allocated lists, `Packet`'s own ordering,
+// and `RawPacket` comparing the text directly.
+//
+//     cargo run --release -p packet --example compare -- [packet count]
+
+use std::{cmp::Ordering, env, time::Instant};
+
+use anyhow::Result;
+use common::Generator;
+use packet::{Packet, RawPacket};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+fn allocating_cmp(left: &Packet, right: &Packet) -> Ordering {
+    match (left, right) {
+        (Packet::Number(l), Packet::Number(r)) => l.cmp(r),
+        (Packet::Number(l), Packet::List(_)) => {
+            allocating_cmp(&Packet::List(vec![Packet::Number(l.clone())]), right)
+        }
+        (Packet::List(_), Packet::Number(r)) => {
+            allocating_cmp(left, &Packet::List(vec![Packet::Number(r.clone())]))
+        }
+        (Packet::List(l), Packet::List(r)) => {
+            for (l, r) in l.iter().zip(r.iter()) {
+                match allocating_cmp(l, r) {
+                    Ordering::Equal => {}
+                    o => return o,
+                }
+            }
+
+            l.len().cmp(&r.len())
+        }
+    }
+}
+
+fn time<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    println!("{name:<28} {:>10.2?}", start.elapsed());
+
+    result
+}
+
+fn main() -> Result<()> {
+    let count = match env::args().nth(1) {
+        Some(count) => count.parse()?,
+        None => 200_000,
+    };
+
+    // Small numbers and frequent nesting, so comparisons often have to mix
+    // numbers with lists before finding a difference.
+    let mut generator = Generator::new(2022, 4).shape(5, 5, 2);
+    let lines = (0..count)
+        .map(|_| generator.packet(0).to_string())
+        .collect::<Vec<_>>();
+    println!(
+        "{count} packets, {} bytes",
+        lines.iter().map(String::len).sum::<usize>()
+    );
+
+    let packets = time("parse", || {
+        lines
+            .iter()
+            .map(|line| line.parse::<Packet>())
+            .collect::<Result<Vec<_>>>()
+    })?;
+    let raw = time("validate raw", || {
+        lines
+            .iter()
+            .map(|line| RawPacket::new(line))
+            .collect::<Result<Vec<_>>>()
+    })?;
+
+    let mut allocating = packets.clone();
+    time("sort, allocating compare", || {
+        allocating.sort_by(allocating_cmp)
+    });
+    let mut borrowed = packets;
+    time("sort, borrowed compare", || borrowed.sort());
+    let mut raw = raw;
+    time("sort, raw text compare", || raw.sort());
+
+    let agree = allocating
+        .iter()
+        .zip(borrowed.iter())
+        .zip(raw.iter())
+        .all(|((a, b), r)| a.cmp(b) == Ordering::Equal && r.to_packet().cmp(b) == Ordering::Equal);
+    println!("orders agree: {agree}");
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use anyhow::{bail, Result};
+
+// An integer of any size, kept as its decimal digits. Digits never have
+// leading zeros and zero is never negative, so equal values compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Integer {
+    negative: bool,
+    digits: String,
+}
+
+impl Integer {
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_string().parse().ok()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        self.to_string().parse().ok()
+    }
+
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        self.digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.cmp(&other.digits))
+    }
+}
+
+impl FromStr for Integer {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            bail!("'{}' is not an integer", s);
+        }
+
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0",
+            trimmed => trimmed,
+        };
+
+        Ok(Self {
+            negative: negative && digits != "0",
+            digits: digits.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.digits)
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Integer {
+                fn from(value: $t) -> Self {
+                    Self {
+                        negative: value < 0,
+                        digits: value.unsigned_abs().to_string(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_from_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Integer {
+                fn from(value: $t) -> Self {
+                    Self {
+                        negative: false,
+                        digits: value.to_string(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(i32, i64, i128, isize);
+impl_from_unsigned!(u32, u64, u128, usize);
+
+// Splits a decimal literal into its sign and significant digits; zero has
+// no digits and is never negative.
+fn significant(literal: &[u8]) -> (bool, &[u8]) {
+    let (negative, digits) = match literal.split_first() {
+        Some((b'-', digits)) => (true, digits),
+        _ => (false, literal),
+    };
+    let start = digits
+        .iter()
+        .position(|d| *d != b'0')
+        .unwrap_or(digits.len());
+    let digits = &digits[start..];
+
+    (negative && !digits.is_empty(), digits)
+}
+
+// Compares two decimal literals by value without parsing them.
+pub(crate) fn cmp_literals(a: &[u8], b: &[u8]) -> Ordering {
+    let magnitude = |a: &[u8], b: &[u8]| a.len().cmp(&b.len()).then_with(|| a.cmp(b));
+
+    match (significant(a), significant(b)) {
+        ((false, a), (false, b)) => magnitude(a, b),
+        ((true, a), (true, b)) => magnitude(b, a),
+        ((false, _), (true, _)) => Ordering::Greater,
+        ((true, _), (false, _)) => Ordering::Less,
+    }
+}
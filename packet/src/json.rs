@@ -0,0 +1,171 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{value::RawValue, Number, Value};
+
+use crate::{Integer, Packet};
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+// Packets are always lists, so only an array can stand at the top level.
+fn check_root(value: &Value) -> Result<()> {
+    if !value.is_array() {
+        bail!("Expected an array at $, got {}", describe(value));
+    }
+
+    Ok(())
+}
+
+// `path` is a JSONPath-style location used in error messages, e.g. `$[1][0]`.
+fn from_value(value: &Value, path: &mut String) -> Result<Packet> {
+    match value {
+        Value::Number(n) => match Integer::from_str(&n.to_string()) {
+            Ok(integer) => Ok(Packet::Number(integer)),
+            Err(_) => bail!("Expected an integer at {}, got {}", path, n),
+        },
+        Value::Array(items) => {
+            let mut packets = vec![];
+            for (idx, item) in items.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{idx}]"));
+                packets.push(from_value(item, path)?);
+                path.truncate(len);
+            }
+            Ok(Packet::List(packets))
+        }
+        other => bail!(
+            "Expected an integer or an array at {}, got {}",
+            path,
+            describe(other)
+        ),
+    }
+}
+
+// Works on the raw JSON text so integers of any size survive; `Value` only
+// holds numbers that fit 64 bits.
+fn from_raw(raw: &RawValue, path: &mut String) -> Result<Packet> {
+    let text = raw.get();
+    if text.starts_with('[') {
+        let items = serde_json::from_str::<Vec<&RawValue>>(text)?;
+        let mut packets = vec![];
+        for (idx, item) in items.into_iter().enumerate() {
+            let len = path.len();
+            path.push_str(&format!("[{idx}]"));
+            packets.push(from_raw(item, path)?);
+            path.truncate(len);
+        }
+        return Ok(Packet::List(packets));
+    }
+    if let Ok(integer) = Integer::from_str(text) {
+        return Ok(Packet::Number(integer));
+    }
+
+    from_value(&serde_json::from_str(text)?, path)
+}
+
+impl TryFrom<&Value> for Packet {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Value) -> Result<Self> {
+        check_root(value)?;
+
+        from_value(value, &mut "$".to_owned())
+    }
+}
+
+impl TryFrom<Value> for Packet {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        Packet::try_from(&value)
+    }
+}
+
+fn to_number(integer: &Integer) -> Result<Number> {
+    integer
+        .to_i64()
+        .map(Number::from)
+        .or_else(|| integer.to_u64().map(Number::from))
+        .ok_or_else(|| anyhow!("{} doesn't fit in a 64-bit JSON number", integer))
+}
+
+// Fails for numbers beyond 64 bits, which `Value` can't hold exactly; use
+// `to_json` for those.
+impl TryFrom<&Packet> for Value {
+    type Error = anyhow::Error;
+
+    fn try_from(packet: &Packet) -> Result<Self> {
+        Ok(match packet {
+            Packet::Number(n) => Value::Number(to_number(n)?),
+            Packet::List(l) => Value::Array(l.iter().map(Value::try_from).collect::<Result<_>>()?),
+        })
+    }
+}
+
+impl TryFrom<Packet> for Value {
+    type Error = anyhow::Error;
+
+    fn try_from(packet: Packet) -> Result<Self> {
+        Value::try_from(&packet)
+    }
+}
+
+impl Packet {
+    pub fn from_json(s: &str) -> Result<Self> {
+        let raw =
+            serde_json::from_str::<&RawValue>(s).map_err(|e| anyhow!("Invalid JSON: {}", e))?;
+        if !raw.get().starts_with('[') {
+            check_root(&serde_json::from_str(raw.get())?)?;
+        }
+
+        from_raw(raw, &mut "$".to_owned())
+    }
+
+    // The packet notation is already valid JSON.
+    pub fn to_json(&self) -> String {
+        self.to_string()
+    }
+}
+
+// Numbers that fit a 64-bit integer are serialized as one. Larger ones are
+// written as raw JSON number tokens, which only serde_json understands.
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Packet::Number(n) => {
+                if let Some(v) = n.to_i64() {
+                    serializer.serialize_i64(v)
+                } else if let Some(v) = n.to_u64() {
+                    serializer.serialize_u64(v)
+                } else {
+                    RawValue::from_string(n.to_string())
+                        .map_err(ser::Error::custom)?
+                        .serialize(serializer)
+                }
+            }
+            Packet::List(l) => serializer.collect_seq(l),
+        }
+    }
+}
+
+// serde_json hands numbers beyond 64 bits to deserializers as floats, so
+// packets are read from the raw JSON text instead, the same way `from_json`
+// reads them. Turning on serde_json's `arbitrary_precision` would avoid
+// that, but cargo would then enable it for every crate in the workspace.
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+
+        Packet::from_json(raw.get()).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,132 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+
+mod integer;
+mod json;
+mod raw;
+mod set;
+
+pub use integer::Integer;
+pub use raw::RawPacket;
+pub use set::PacketSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    Number(Integer),
+    List(Vec<Packet>),
+}
+
+impl Packet {
+    // A divider packet, `[[n]]`.
+    pub fn divider(n: impl Into<Integer>) -> Self {
+        Packet::List(vec![Packet::List(vec![Packet::Number(n.into())])])
+    }
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Number(n) => write!(f, "{n}"),
+            Packet::List(l) => {
+                write!(f, "[")?;
+                for (idx, packet) in l.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{packet}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A number compared with a list is treated as a one element list, which a
+// borrowed one element slice stands in for without allocating.
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Number(l), Packet::Number(r)) => l.cmp(r),
+            (Packet::Number(_), Packet::List(r)) => std::slice::from_ref(self).cmp(r),
+            (Packet::List(l), Packet::Number(_)) => l.as_slice().cmp(std::slice::from_ref(other)),
+            (Packet::List(l), Packet::List(r)) => l.cmp(r),
+        }
+    }
+}
+
+mod parser {
+    use nom::{
+        branch::alt,
+        character::complete::{char, digit1, space0},
+        combinator::{all_consuming, cut, map, map_res, opt, recognize},
+        multi::separated_list0,
+        sequence::{delimited, pair, preceded, terminated},
+        IResult,
+    };
+
+    use super::Packet;
+
+    fn number(i: &str) -> IResult<&str, Packet> {
+        map(
+            map_res(recognize(pair(opt(char('-')), digit1)), str::parse),
+            Packet::Number,
+        )(i)
+    }
+
+    fn item(i: &str) -> IResult<&str, Packet> {
+        alt((number, list))(i)
+    }
+
+    // Once a list is opened it has to close, so errors point at the place
+    // the list went wrong rather than at its opening bracket.
+    fn list(i: &str) -> IResult<&str, Packet> {
+        map(
+            preceded(
+                pair(char('['), space0),
+                cut(terminated(
+                    separated_list0(delimited(space0, char(','), space0), item),
+                    pair(space0, char(']')),
+                )),
+            ),
+            Packet::List,
+        )(i)
+    }
+
+    // On failure, returns how far into the input parsing got.
+    pub fn packet(i: &str) -> Result<Packet, usize> {
+        match all_consuming(delimited(space0, list, space0))(i) {
+            Ok((_, packet)) => Ok(packet),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(i.len() - e.input.len()),
+            Err(nom::Err::Incomplete(_)) => Err(i.len()),
+        }
+    }
+}
+
+// The canonical packet grammar: a bracketed, comma separated list of
+// integers and lists. Spaces are allowed between tokens, but trailing commas
+// and anything after the closing bracket are not.
+impl FromStr for Packet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parser::packet(s)
+            .map_err(|column| anyhow!("Invalid packet '{}' at column {}", s, column + 1))
+    }
+}
+
+// Parses one packet per non-blank line.
+pub fn parse_lines(lines: &[String]) -> Result<Vec<Packet>> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| line.parse().map_err(|e| anyhow!("line {}: {}", idx + 1, e)))
+        .collect()
+}
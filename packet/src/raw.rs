@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{integer::cmp_literals, Packet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Comma,
+    Number(&'a [u8]),
+}
+
+// Splits packet text into tokens, skipping spaces. Yields the byte offset of
+// each token, or of the first byte that can't start one.
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            bytes: text.as_bytes(),
+            pos: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<(usize, Token<'a>), usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.bytes.get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+
+        let start = self.pos;
+        let token = match self.bytes.get(start)? {
+            b'[' => Token::Open,
+            b']' => Token::Close,
+            b',' => Token::Comma,
+            b'-' | b'0'..=b'9' => {
+                let digits = start + usize::from(self.bytes[start] == b'-');
+                let end = self.bytes[digits..]
+                    .iter()
+                    .position(|b| !b.is_ascii_digit())
+                    .map_or(self.bytes.len(), |len| digits + len);
+                if end == digits {
+                    return Some(Err(digits));
+                }
+                self.pos = end;
+                return Some(Ok((start, Token::Number(&self.bytes[start..end]))));
+            }
+            _ => return Some(Err(start)),
+        };
+        self.pos += 1;
+
+        Some(Ok((start, token)))
+    }
+}
+
+// Checks `text` against the same grammar as `Packet::from_str` without
+// building the packet.
+fn validate(text: &str) -> Result<()> {
+    let error = |pos: usize| anyhow!("Invalid packet '{}' at column {}", text, pos + 1);
+    let mut depth = 0;
+    let mut previous = None;
+
+    for token in Tokens::new(text) {
+        let (pos, token) = token.map_err(error)?;
+        let allowed = match (previous, token) {
+            (_, _) if depth == 0 && previous.is_some() => false,
+            (None, Token::Open) => true,
+            (None, _) => false,
+            (Some(Token::Open | Token::Comma), Token::Open | Token::Number(_)) => true,
+            (Some(Token::Number(_) | Token::Close), Token::Comma | Token::Close) => true,
+            (Some(Token::Open), Token::Close) => true,
+            _ => false,
+        };
+        if !allowed {
+            return Err(error(pos));
+        }
+
+        match token {
+            Token::Open => depth += 1,
+            Token::Close => depth -= 1,
+            _ => {}
+        }
+        previous = Some(token);
+    }
+
+    if depth != 0 || previous.is_none() {
+        bail!(error(text.len()));
+    }
+
+    Ok(())
+}
+
+// One side of a comparison. A number compared with a list is replayed as a
+// one element list: the number is handed out again, followed by the closing
+// brackets of however many lists it has been wrapped in.
+struct Side<'a> {
+    tokens: Tokens<'a>,
+    wrapped: Option<&'a [u8]>,
+    closes: usize,
+}
+
+impl<'a> Side<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            tokens: Tokens::new(text),
+            wrapped: None,
+            closes: 0,
+        }
+    }
+
+    fn wrap(&mut self, number: &'a [u8]) {
+        self.wrapped = Some(number);
+        self.closes += 1;
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if let Some(number) = self.wrapped.take() {
+            return Some(Token::Number(number));
+        }
+        if self.closes > 0 {
+            self.closes -= 1;
+            return Some(Token::Close);
+        }
+
+        self.tokens
+            .by_ref()
+            .filter_map(|token| token.ok())
+            .map(|(_, token)| token)
+            .find(|token| *token != Token::Comma)
+    }
+}
+
+fn compare(left: &str, right: &str) -> Ordering {
+    let mut left = Side::new(left);
+    let mut right = Side::new(right);
+
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(Token::Open), Some(Token::Open)) | (Some(Token::Close), Some(Token::Close)) => {}
+            (Some(Token::Number(l)), Some(Token::Number(r))) => match cmp_literals(l, r) {
+                Ordering::Equal => {}
+                o => return o,
+            },
+            (Some(Token::Open), Some(Token::Number(r))) => right.wrap(r),
+            (Some(Token::Number(l)), Some(Token::Open)) => left.wrap(l),
+            (Some(Token::Close) | None, _) => return Ordering::Less,
+            (_, Some(Token::Close) | None) => return Ordering::Greater,
+            _ => unreachable!("commas are skipped"),
+        }
+    }
+}
+
+// A packet compared straight from its text, without building a tree.
+// Equality follows the packet ordering, so `[1]` equals `[[1]]`.
+#[derive(Debug, Clone, Copy)]
+pub struct RawPacket<'a> {
+    text: &'a str,
+}
+
+impl<'a> RawPacket<'a> {
+    pub fn new(text: &'a str) -> Result<Self> {
+        validate(text)?;
+
+        Ok(Self { text })
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn to_packet(&self) -> Packet {
+        self.text.parse().expect("raw packets are validated")
+    }
+}
+
+impl PartialEq for RawPacket<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RawPacket<'_> {}
+
+impl PartialOrd for RawPacket<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RawPacket<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(self.text, other.text)
+    }
+}
@@ -0,0 +1,67 @@
+use crate::Packet;
+
+// Answers where packets would land in sorted order without sorting. A
+// packet's position counts only packets that order strictly before it, so
+// one that ties with packets in the set takes the first of the tied slots.
+#[derive(Debug, Clone, Default)]
+pub struct PacketSet {
+    packets: Vec<Packet>,
+}
+
+impl PacketSet {
+    pub fn new(packets: Vec<Packet>) -> Self {
+        Self { packets }
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    pub fn packets(&self) -> &[Packet] {
+        &self.packets
+    }
+
+    // The 1-based position `packet` would take if it were added to the set.
+    pub fn position(&self, packet: &Packet) -> usize {
+        1 + self.packets.iter().filter(|p| *p < packet).count()
+    }
+
+    // The 1-based positions `dividers` take when they're all added to the
+    // set, in the order given. Dividers that tie with each other keep their
+    // relative order. Makes a single pass over the set.
+    pub fn positions(&self, dividers: &[Packet]) -> Vec<usize> {
+        let mut order = (0..dividers.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| dividers[*a].cmp(&dividers[*b]));
+
+        // before[i] counts packets that sort before the i-th smallest divider
+        // but not before the one preceding it.
+        let mut before = vec![0; dividers.len() + 1];
+        for packet in self.packets.iter() {
+            before[order.partition_point(|d| dividers[*d] <= *packet)] += 1;
+        }
+
+        let mut positions = vec![0; dividers.len()];
+        let mut smaller = 0;
+        for (rank, divider) in order.into_iter().enumerate() {
+            smaller += before[rank];
+            positions[divider] = 1 + smaller + rank;
+        }
+
+        positions
+    }
+
+    // The puzzle's decoder key: the product of the dividers' positions.
+    pub fn decoder_key(&self, dividers: &[Packet]) -> usize {
+        self.positions(dividers).into_iter().product()
+    }
+}
+
+impl FromIterator<Packet> for PacketSet {
+    fn from_iter<I: IntoIterator<Item = Packet>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
@@ -0,0 +1,62 @@
+// Deterministic pseudo-random packets for the tests and the comparison
+// example, so failures and timings are reproducible. Each test crate uses a
+// different part of it.
+#![allow(dead_code)]
+
+use packet::Packet;
+
+pub struct Generator {
+    state: u64,
+    numbers: u64,
+    max_len: u64,
+    max_depth: usize,
+    nest_one_in: u64,
+}
+
+impl Generator {
+    // Numbers are drawn from `0..numbers`; a small range makes packets share
+    // long prefixes.
+    pub fn new(seed: u64, numbers: u64) -> Self {
+        Self {
+            state: seed,
+            numbers,
+            max_len: 4,
+            max_depth: 4,
+            nest_one_in: 3,
+        }
+    }
+
+    // Lists hold up to `max_len` items, each of which is a nested list one
+    // time in `nest_one_in` until `max_depth` is reached.
+    pub fn shape(self, max_len: u64, max_depth: usize, nest_one_in: u64) -> Self {
+        Self {
+            max_len,
+            max_depth,
+            nest_one_in,
+            ..self
+        }
+    }
+
+    fn next(&mut self, bound: u64) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 33) % bound
+    }
+
+    pub fn packet(&mut self, depth: usize) -> Packet {
+        let len = self.next(self.max_len + 1);
+        Packet::List(
+            (0..len)
+                .map(|_| {
+                    if depth < self.max_depth && self.next(self.nest_one_in) == 0 {
+                        self.packet(depth + 1)
+                    } else {
+                        Packet::Number(self.next(self.numbers).into())
+                    }
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,190 @@
+// Checks the canonical parser against the two parsers day13 used before it:
+// the pest grammar from part 1 and the nom combinators from part 2. Inputs
+// both of them accepted must parse to the same packet, and inputs only one
+// of them accepted must be rejected.
+
+use common::Generator;
+use packet::Packet;
+
+mod common;
+
+mod pest_reference {
+    use pest::{iterators::Pair, Parser};
+    use pest_derive::Parser;
+
+    use packet::Packet;
+
+    #[derive(Parser)]
+    #[grammar_inline = r#"
+digit = { '0'..'9' }
+number = { digit+ }
+WHITESPACE = _{ " " }
+list = { "[" ~ ((number | list) ~ ","?)* ~ "]" }
+"#]
+    struct ListParser;
+
+    // `number` isn't atomic, so pest matches "1 2" as a single number that
+    // then fails to convert.
+    fn parse_list(outer: Pair<Rule>) -> Option<Packet> {
+        let mut packets = vec![];
+
+        for pair in outer.into_inner() {
+            match pair.as_rule() {
+                Rule::number => packets.push(Packet::Number(pair.as_str().parse().ok()?)),
+                Rule::list => packets.push(parse_list(pair)?),
+                Rule::WHITESPACE | Rule::digit => unreachable!(),
+            }
+        }
+
+        Some(Packet::List(packets))
+    }
+
+    pub fn parse(line: &str) -> Option<Packet> {
+        let parsed = ListParser::parse(Rule::list, line).ok()?;
+
+        parsed.into_iter().next().and_then(parse_list)
+    }
+}
+
+mod nom_reference {
+    use packet::Packet;
+
+    fn number(i: &str) -> nom::IResult<&str, Packet> {
+        let (rest, number) =
+            nom::bytes::complete::take_while1(|c: char| nom::character::is_digit(c as u8))(i)?;
+
+        Ok((rest, Packet::Number(number.parse().unwrap())))
+    }
+
+    fn list_item(i: &str) -> nom::IResult<&str, Packet> {
+        nom::branch::alt((number, list))(i)
+    }
+
+    fn inside_list(i: &str) -> nom::IResult<&str, Vec<Packet>> {
+        nom::multi::separated_list0(nom::bytes::complete::tag(","), list_item)(i)
+    }
+
+    fn list(i: &str) -> nom::IResult<&str, Packet> {
+        let (rest, parsed) = nom::sequence::delimited(
+            nom::bytes::complete::tag("["),
+            inside_list,
+            nom::bytes::complete::tag("]"),
+        )(i)?;
+
+        Ok((rest, Packet::List(parsed)))
+    }
+
+    pub fn parse(line: &str) -> Option<Packet> {
+        match list(line) {
+            Ok(("", parsed)) => Some(parsed),
+            _ => None,
+        }
+    }
+}
+
+const EXAMPLE: &str = "\
+[1,1,3,1,1]
+[1,1,5,1,1]
+[[1],[2,3,4]]
+[[1],4]
+[9]
+[[8,7,6]]
+[[4,4],4,4]
+[[4,4],4,4,4]
+[7,7,7,7]
+[7,7,7]
+[]
+[3]
+[[[]]]
+[[]]
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+#[test]
+fn parses_the_puzzle_example_like_both_reference_parsers() {
+    for line in EXAMPLE.lines() {
+        let packet = line.parse::<Packet>().unwrap();
+        assert_eq!(
+            Some(&packet),
+            pest_reference::parse(line).as_ref(),
+            "{line}"
+        );
+        assert_eq!(Some(&packet), nom_reference::parse(line).as_ref(), "{line}");
+    }
+}
+
+#[test]
+fn agrees_with_both_reference_parsers_on_generated_packets() {
+    let mut generator = Generator::new(13, 20);
+    for _ in 0..2000 {
+        let line = generator.packet(0).to_string();
+        let packet = line.parse::<Packet>().unwrap();
+        assert_eq!(
+            Some(&packet),
+            pest_reference::parse(&line).as_ref(),
+            "{line}"
+        );
+        assert_eq!(
+            Some(&packet),
+            nom_reference::parse(&line).as_ref(),
+            "{line}"
+        );
+    }
+}
+
+#[test]
+fn display_round_trips() {
+    let mut generator = Generator::new(7, 20);
+    for line in EXAMPLE.lines() {
+        assert_eq!(line.parse::<Packet>().unwrap().to_string(), line);
+    }
+    for _ in 0..2000 {
+        let packet = generator.packet(0);
+        assert_eq!(packet.to_string().parse::<Packet>().unwrap(), packet);
+    }
+}
+
+#[test]
+fn rejects_inputs_only_one_reference_parser_accepted() {
+    for line in ["[1,]", "[1,2,]", "[[],]", "[[][]]", "[1]]", "[1]x"] {
+        assert!(pest_reference::parse(line).is_some(), "{line}");
+        assert!(nom_reference::parse(line).is_none(), "{line}");
+        assert!(line.parse::<Packet>().is_err(), "{line}");
+    }
+}
+
+#[test]
+fn rejects_malformed_packets() {
+    for line in ["", "[", "]", "1", "[1,,2]", "[,1]", "[a]", "[[1]"] {
+        assert!(pest_reference::parse(line).is_none(), "{line}");
+        assert!(nom_reference::parse(line).is_none(), "{line}");
+        assert!(line.parse::<Packet>().is_err(), "{line}");
+    }
+}
+
+#[test]
+fn allows_spaces_between_tokens() {
+    let packet = " [ 1 , [ 2 ] , [ ] ] ".parse::<Packet>().unwrap();
+    assert_eq!(packet.to_string(), "[1,[2],[]]");
+
+    // pest only skipped spaces where they couldn't end up inside a number.
+    let line = "[ 1, [ 2], [ ]]";
+    assert_eq!(pest_reference::parse(line), Some(packet.clone()));
+    assert_eq!(line.parse::<Packet>().unwrap(), packet);
+}
+
+#[test]
+fn reports_where_parsing_failed() {
+    let error = "[1,[2,x]]".parse::<Packet>().unwrap_err();
+    assert_eq!(error.to_string(), "Invalid packet '[1,[2,x]]' at column 6");
+}
+
+#[test]
+fn parses_negative_and_large_numbers() {
+    let line = "[-3,[18446744073709551616],-0,007]";
+    let packet = line.parse::<Packet>().unwrap();
+    assert_eq!(packet.to_string(), "[-3,[18446744073709551616],0,7]");
+    assert!("[-3]".parse::<Packet>().unwrap() < "[-2]".parse().unwrap());
+    assert!("[99999999999999999999]".parse::<Packet>().unwrap() > "[1]".parse().unwrap());
+    assert!("[-99999999999999999999]".parse::<Packet>().unwrap() < "[-1]".parse().unwrap());
+}
@@ -0,0 +1,107 @@
+use packet::Packet;
+use serde_json::{json, Value};
+
+#[test]
+fn converts_to_and_from_json_values() {
+    let packet = "[1,[2,[]],-3]".parse::<Packet>().unwrap();
+    let value = Value::try_from(&packet).unwrap();
+    assert_eq!(value, json!([1, [2, []], -3]));
+    assert_eq!(Packet::try_from(value.clone()).unwrap(), packet);
+    assert_eq!(serde_json::from_value::<Packet>(value).unwrap(), packet);
+}
+
+#[test]
+fn keeps_numbers_beyond_64_bits() {
+    let line = "[123456789012345678901234567890,-123456789012345678901234567890]";
+    let packet = line.parse::<Packet>().unwrap();
+    assert_eq!(packet.to_json(), line);
+    assert_eq!(Packet::from_json(line).unwrap(), packet);
+    assert_eq!(serde_json::to_string(&packet).unwrap(), line);
+    assert_eq!(Packet::from_json(&format!(" {line} ")).unwrap(), packet);
+}
+
+#[test]
+fn refuses_to_lose_precision_in_values() {
+    let line = "[123456789012345678901234567890]";
+    let packet = line.parse::<Packet>().unwrap();
+    assert!(Value::try_from(&packet).is_err());
+    assert_eq!(
+        Packet::try_from(json!([18446744073709551615u64, -9223372036854775808i64])).unwrap(),
+        "[18446744073709551615,-9223372036854775808]"
+            .parse()
+            .unwrap()
+    );
+}
+
+#[test]
+fn round_trips_numbers_beyond_64_bits_through_serde() {
+    let line = "[[123456789012345678901234567890],-123456789012345678901234567890,[[7]]]";
+    let packet = line.parse::<Packet>().unwrap();
+    let json = serde_json::to_string(&packet).unwrap();
+    assert_eq!(serde_json::from_str::<Packet>(&json).unwrap(), packet);
+    assert_eq!(
+        serde_json::from_reader::<_, Packet>(json.as_bytes()).unwrap(),
+        packet
+    );
+
+    // Packets nested in other serialized data come back too.
+    let packets = vec![packet.clone(), Packet::divider(2)];
+    let json = serde_json::to_string(&packets).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<Packet>>(&json).unwrap(), packets);
+}
+
+#[test]
+fn only_accepts_arrays_at_the_top_level() {
+    for json in ["5", "-1", " 7 "] {
+        let message = "Expected an array at $, got a number";
+        assert_eq!(Packet::from_json(json).unwrap_err().to_string(), message);
+        assert!(serde_json::from_str::<Packet>(json)
+            .unwrap_err()
+            .to_string()
+            .starts_with(message));
+    }
+    assert!(Packet::try_from(json!(5)).is_err());
+
+    // Every packet read from JSON must survive a trip through the packet
+    // notation.
+    for json in ["[]", "[5]", "[[1],2,[[3]]]"] {
+        let packet = Packet::from_json(json).unwrap();
+        assert_eq!(packet.to_string().parse::<Packet>().unwrap(), packet);
+    }
+}
+
+#[test]
+fn round_trips_through_serde() {
+    let packet = "[[4,4],4,[],[[-1]]]".parse::<Packet>().unwrap();
+    let json = serde_json::to_string(&packet).unwrap();
+    assert_eq!(json, "[[4,4],4,[],[[-1]]]");
+    assert_eq!(serde_json::from_str::<Packet>(&json).unwrap(), packet);
+}
+
+#[test]
+fn rejects_values_that_are_not_packets() {
+    let cases = [
+        ("[1,[2,1.5]]", "Expected an integer at $[1][1], got 1.5"),
+        (
+            "[1,\"2\"]",
+            "Expected an integer or an array at $[1], got a string",
+        ),
+        ("{\"a\":1}", "Expected an array at $, got an object"),
+        (
+            "[[null]]",
+            "Expected an integer or an array at $[0][0], got null",
+        ),
+        (
+            "[true]",
+            "Expected an integer or an array at $[0], got a boolean",
+        ),
+        ("[2.0]", "Expected an integer at $[0], got 2.0"),
+    ];
+    for (json, message) in cases {
+        assert_eq!(Packet::from_json(json).unwrap_err().to_string(), message);
+        assert!(serde_json::from_str::<Packet>(json)
+            .unwrap_err()
+            .to_string()
+            .starts_with(message));
+    }
+}
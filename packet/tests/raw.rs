@@ -0,0 +1,111 @@
+use std::cmp::Ordering;
+
+use common::Generator;
+use packet::{Packet, RawPacket};
+
+mod common;
+
+#[test]
+fn orders_like_parsed_packets() {
+    let mut generator = Generator::new(43, 3);
+    let lines = (0..400)
+        .map(|_| generator.packet(0).to_string())
+        .collect::<Vec<_>>();
+    let packets = lines
+        .iter()
+        .map(|line| line.parse::<Packet>().unwrap())
+        .collect::<Vec<_>>();
+    let raw = lines
+        .iter()
+        .map(|line| RawPacket::new(line).unwrap())
+        .collect::<Vec<_>>();
+
+    for i in 0..lines.len() {
+        for j in 0..lines.len() {
+            assert_eq!(
+                raw[i].cmp(&raw[j]),
+                packets[i].cmp(&packets[j]),
+                "{} vs {}",
+                lines[i],
+                lines[j]
+            );
+        }
+    }
+}
+
+#[test]
+fn compares_numbers_against_lists() {
+    let cases = [
+        ("[1]", "[[1]]", Ordering::Equal),
+        ("[[[1]]]", "[1]", Ordering::Equal),
+        ("[[1],4]", "[1,[4]]", Ordering::Equal),
+        ("[[1],[2,3,4]]", "[[1],4]", Ordering::Less),
+        ("[9]", "[[8,7,6]]", Ordering::Greater),
+        ("[[[]]]", "[[]]", Ordering::Greater),
+        ("[1]", "[[1,2]]", Ordering::Less),
+        ("[[[2]],1]", "[2,0]", Ordering::Greater),
+        ("[-1]", "[0]", Ordering::Less),
+        ("[007]", "[7]", Ordering::Equal),
+        ("[-0]", "[0]", Ordering::Equal),
+        ("[ 1 , [ 2 ] ]", "[1,2]", Ordering::Equal),
+        (
+            "[100000000000000000000]",
+            "[99999999999999999999]",
+            Ordering::Greater,
+        ),
+        (
+            "[-100000000000000000000]",
+            "[-99999999999999999999]",
+            Ordering::Less,
+        ),
+    ];
+    for (left, right, expected) in cases {
+        let raw = RawPacket::new(left)
+            .unwrap()
+            .cmp(&RawPacket::new(right).unwrap());
+        let parsed = left
+            .parse::<Packet>()
+            .unwrap()
+            .cmp(&right.parse::<Packet>().unwrap());
+        assert_eq!(raw, expected, "{left} vs {right}");
+        assert_eq!(parsed, expected, "{left} vs {right}");
+    }
+}
+
+#[test]
+fn accepts_the_same_text_as_the_parser() {
+    let lines = [
+        "[]",
+        "[[]]",
+        "[1,2]",
+        " [ 1 , [ 2 ] ] ",
+        "[-5,[0]]",
+        "",
+        "[",
+        "]",
+        "1",
+        "[1,]",
+        "[,1]",
+        "[1,,2]",
+        "[[],]",
+        "[[][]]",
+        "[1]]",
+        "[1]x",
+        "[-]",
+        "[1-2]",
+        "[[1]",
+        "[a]",
+        "[1 2]",
+        "[]]",
+        "[][]",
+    ];
+    for line in lines {
+        let raw = RawPacket::new(line);
+        let parsed = line.parse::<Packet>();
+        assert_eq!(raw.is_ok(), parsed.is_ok(), "{line}");
+        if let Ok(raw) = raw {
+            assert_eq!(raw.to_packet(), parsed.unwrap());
+            assert_eq!(raw.as_str(), line);
+        }
+    }
+}
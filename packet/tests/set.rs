@@ -0,0 +1,87 @@
+use common::Generator;
+use packet::{Packet, PacketSet};
+
+mod common;
+
+// Positions found the way day13 used to: add the dividers, sort everything
+// and look for them.
+fn sorted_positions(packets: &[Packet], dividers: &[Packet]) -> Vec<usize> {
+    let mut all = packets
+        .iter()
+        .map(|p| (p, None))
+        .chain(dividers.iter().enumerate().map(|(idx, d)| (d, Some(idx))))
+        .collect::<Vec<_>>();
+    // Dividers go before packets they tie with.
+    all.sort_by(|a, b| a.0.cmp(b.0).then(b.1.is_some().cmp(&a.1.is_some())));
+
+    let mut positions = vec![0; dividers.len()];
+    for (idx, (_, divider)) in all.into_iter().enumerate() {
+        if let Some(divider) = divider {
+            positions[divider] = idx + 1;
+        }
+    }
+
+    positions
+}
+
+#[test]
+fn finds_the_puzzle_dividers() {
+    let packets = "\
+[1,1,3,1,1]
+[1,1,5,1,1]
+[[1],[2,3,4]]
+[[1],4]
+[9]
+[[8,7,6]]
+[[4,4],4,4]
+[[4,4],4,4,4]
+[7,7,7,7]
+[7,7,7]
+[]
+[3]
+[[[]]]
+[[]]
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]"
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect::<PacketSet>();
+
+    let dividers = [Packet::divider(2), Packet::divider(6)];
+    assert_eq!(packets.positions(&dividers), vec![10, 14]);
+    assert_eq!(packets.decoder_key(&dividers), 140);
+    assert_eq!(packets.position(&dividers[0]), 10);
+    assert_eq!(packets.position(&dividers[1]), 13);
+}
+
+#[test]
+fn matches_sorting_for_arbitrary_dividers() {
+    let mut generator = Generator::new(5, 4);
+    for _ in 0..50 {
+        let packets = (0..200).map(|_| generator.packet(0)).collect::<Vec<_>>();
+        let dividers = (0..7).map(|_| generator.packet(0)).collect::<Vec<_>>();
+        let set = PacketSet::new(packets.clone());
+
+        assert_eq!(
+            set.positions(&dividers),
+            sorted_positions(&packets, &dividers)
+        );
+        for divider in dividers.iter() {
+            assert_eq!(
+                set.position(divider),
+                sorted_positions(&packets, std::slice::from_ref(divider))[0]
+            );
+        }
+    }
+}
+
+#[test]
+fn keeps_tied_dividers_in_order() {
+    let set = ["[1]", "[3]"]
+        .into_iter()
+        .map(|line| line.parse().unwrap())
+        .collect::<PacketSet>();
+    let dividers = ["[2]", "[[2]]", "[1]", "[4]"].map(|line| line.parse::<Packet>().unwrap());
+
+    assert_eq!(set.positions(&dividers), vec![3, 4, 1, 6]);
+}
@@ -0,0 +1,669 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+mod parser {
+    use nom::{
+        bytes::complete::tag,
+        character::complete::{digit1, space0, space1},
+        combinator::{all_consuming, map_res},
+        sequence::tuple,
+        IResult,
+    };
+
+    use super::Move;
+
+    fn number(i: &str) -> IResult<&str, usize> {
+        map_res(digit1, str::parse)(i)
+    }
+
+    fn mv(i: &str) -> IResult<&str, Move> {
+        let (i, (_, _, _, count, _, _, _, from, _, _, _, to, _)) = tuple((
+            space0,
+            tag("move"),
+            space1,
+            number,
+            space1,
+            tag("from"),
+            space1,
+            number,
+            space1,
+            tag("to"),
+            space1,
+            number,
+            space0,
+        ))(i)?;
+
+        Ok((i, Move { count, from, to }))
+    }
+
+    pub fn parse_move(i: &str) -> Option<Move> {
+        all_consuming(mv)(i).ok().map(|(_, mv)| mv)
+    }
+}
+
+impl FromStr for Move {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parser::parse_move(s).ok_or_else(|| {
+            anyhow!(
+                "expected 'move <count> from <stack> to <stack>', got '{}'",
+                s
+            )
+        })
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+// Parses one move per non-blank line. `first_line` is the 1-based line
+// number of `lines[0]`, for error messages.
+pub fn parse_moves(lines: &[String], first_line: usize) -> Result<Vec<Move>> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            line.parse()
+                .map_err(|e| anyhow!("line {}: {}", first_line + idx, e))
+        })
+        .collect()
+}
+
+// Stacks are numbered from 1 and each one is listed bottom crate first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stacks {
+    stacks: Vec<Vec<char>>,
+}
+
+impl Stacks {
+    pub fn new(stacks: Vec<Vec<char>>) -> Self {
+        Self { stacks }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    pub fn get(&self, stack: usize) -> Option<&[char]> {
+        stack
+            .checked_sub(1)
+            .and_then(|idx| self.stacks.get(idx))
+            .map(Vec::as_slice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[char]> {
+        self.stacks.iter().map(Vec::as_slice)
+    }
+
+    // The crate on top of each stack, skipping empty stacks.
+    pub fn tops(&self) -> String {
+        self.stacks.iter().filter_map(|s| s.last()).collect()
+    }
+
+    fn index(&self, stack: usize) -> Result<usize> {
+        match stack.checked_sub(1) {
+            Some(idx) if idx < self.stacks.len() => Ok(idx),
+            _ => bail!("there is no stack {}", stack),
+        }
+    }
+}
+
+// Draws the stacks the way the puzzle input does, with a numbered footer.
+// Columns widen to fit the labels once there are more than 99 stacks.
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.iter().map(|s| s.len()).max().unwrap_or(0);
+        let width = self.len().to_string().len().max(2) + 1;
+
+        for i in (0..height).rev() {
+            for stack in self.iter() {
+                match stack.get(i) {
+                    Some(c) => write!(f, "{:<width$} ", format!("[{c}]"))?,
+                    None => write!(f, "{:width$} ", "")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        for i in 1..=self.len() {
+            write!(f, " {:<w$} ", i, w = width - 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+// How a crane puts down the crates it lifts. `arrange` gets the lifted crates
+// in the order they sat on the source stack, bottom first, and leaves them in
+// the order they end up on the target stack.
+pub trait CraneModel {
+    fn arrange(&self, crates: &mut [char]);
+}
+
+// Moves crates one at a time, so the lifted block ends up upside down.
+pub struct OneAtATime;
+
+impl CraneModel for OneAtATime {
+    fn arrange(&self, crates: &mut [char]) {
+        crates.reverse();
+    }
+}
+
+// Moves the whole block at once, keeping its order.
+pub struct Bulk;
+
+impl CraneModel for Bulk {
+    fn arrange(&self, _crates: &mut [char]) {}
+}
+
+impl<F: Fn(&mut [char])> CraneModel for F {
+    fn arrange(&self, crates: &mut [char]) {
+        self(crates)
+    }
+}
+
+// One applied move and the crates it carried. `lifted` is the order they
+// sat on the source stack and `placed` the order they landed in, both bottom
+// first, so a step can be undone whatever the crane model did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub mv: Move,
+    pub lifted: Vec<char>,
+    pub placed: Vec<char>,
+}
+
+// A compact diff line, e.g. `3 from 1 to 3: ZND -> DNZ`.
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} to {}: ",
+            self.mv.count, self.mv.from, self.mv.to
+        )?;
+        write!(f, "{}", self.lifted.iter().collect::<String>())?;
+        if self.lifted != self.placed {
+            write!(f, " -> {}", self.placed.iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Keeps every applied step. Steps past `applied` have been undone and can be
+// redone until a new move is applied.
+pub struct Crane<M: CraneModel> {
+    model: M,
+    stacks: Stacks,
+    steps: Vec<Step>,
+    applied: usize,
+}
+
+impl<M: CraneModel> Crane<M> {
+    pub fn new(stacks: Stacks, model: M) -> Self {
+        Self {
+            model,
+            stacks,
+            steps: vec![],
+            applied: 0,
+        }
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    pub fn into_stacks(self) -> Stacks {
+        self.stacks
+    }
+
+    // Moves are checked before anything changes, so a bad move leaves the
+    // stacks as they were.
+    pub fn apply(&mut self, mv: &Move) -> Result<()> {
+        let from = self.stacks.index(mv.from)?;
+        let to = self.stacks.index(mv.to)?;
+        let available = self.stacks.stacks[from].len();
+        if mv.count > available {
+            bail!(
+                "can't move {} crates from stack {}, it only has {}",
+                mv.count,
+                mv.from,
+                available
+            );
+        }
+
+        let lifted = self.stacks.stacks[from].split_off(available - mv.count);
+        let mut placed = lifted.clone();
+        self.model.arrange(&mut placed);
+        self.stacks.stacks[to].extend(&placed);
+
+        self.steps.truncate(self.applied);
+        self.steps.push(Step {
+            mv: *mv,
+            lifted,
+            placed,
+        });
+        self.applied += 1;
+
+        Ok(())
+    }
+
+    pub fn run(&mut self, moves: &[Move]) -> Result<()> {
+        for (idx, mv) in moves.iter().enumerate() {
+            self.apply(mv)
+                .map_err(|e| anyhow!("move {}: {}", idx + 1, e))?;
+        }
+
+        Ok(())
+    }
+
+    // The steps that got the stacks to where they are now.
+    pub fn history(&self) -> &[Step] {
+        &self.steps[..self.applied]
+    }
+
+    // How many moves have been applied, which is also the move the stacks
+    // are currently after.
+    pub fn position(&self) -> usize {
+        self.applied
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.applied < self.steps.len()
+    }
+
+    pub fn undo(&mut self) -> Option<&Step> {
+        self.applied = self.applied.checked_sub(1)?;
+        let step = &self.steps[self.applied];
+        let target = &mut self.stacks.stacks[step.mv.to - 1];
+        target.truncate(target.len() - step.placed.len());
+        self.stacks.stacks[step.mv.from - 1].extend(&step.lifted);
+
+        Some(step)
+    }
+
+    pub fn redo(&mut self) -> Option<&Step> {
+        let step = self.steps.get(self.applied)?;
+        let source = &mut self.stacks.stacks[step.mv.from - 1];
+        source.truncate(source.len() - step.lifted.len());
+        self.stacks.stacks[step.mv.to - 1].extend(&step.placed);
+        self.applied += 1;
+
+        Some(step)
+    }
+
+    // Undoes or redoes steps until the stacks are as they were after move
+    // `n`, where move 0 is the starting diagram.
+    pub fn seek(&mut self, n: usize) -> Result<()> {
+        if n > self.steps.len() {
+            bail!(
+                "can't go to move {}, only {} have been made",
+                n,
+                self.steps.len()
+            );
+        }
+        while self.applied > n {
+            self.undo();
+        }
+        while self.applied < n {
+            self.redo();
+        }
+
+        Ok(())
+    }
+
+    // One line per applied step, numbered from 1.
+    pub fn diff_log(&self) -> String {
+        self.history()
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| format!("{}: {}\n", idx + 1, step))
+            .collect()
+    }
+}
+
+// The numbered footer under the diagram, e.g. ` 1   2   3 `. Gives the span
+// of columns each label covers, or None if the line isn't a footer.
+fn footer_labels(line: &str) -> Option<Vec<(usize, usize)>> {
+    let chars = line.chars().collect::<Vec<char>>();
+    let mut labels = vec![];
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos] == ' ' {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while pos < chars.len() && chars[pos] != ' ' {
+            pos += 1;
+        }
+        let label = chars[start..pos].iter().collect::<String>();
+        if label.parse::<usize>().ok()? != labels.len() + 1 {
+            return None;
+        }
+        labels.push((start, pos - 1));
+    }
+
+    (!labels.is_empty()).then_some(labels)
+}
+
+// Finds the stack whose label sits under the crate drawn at columns
+// `start..=start + 2`, preferring the label under the crate letter itself.
+fn stack_under(labels: &[(usize, usize)], start: usize) -> Option<usize> {
+    let overlaps = |(from, to): &(usize, usize), lo: usize, hi: usize| *from <= hi && *to >= lo;
+
+    labels
+        .iter()
+        .position(|label| overlaps(label, start + 1, start + 1))
+        .or_else(|| {
+            labels
+                .iter()
+                .position(|label| overlaps(label, start, start + 2))
+        })
+}
+
+// Reads the stack diagram at the top of the puzzle input, returning the
+// stacks and the number of lines the diagram and its footer took up.
+// Columns are found from the footer, so rows may be ragged and stacks may
+// have multi-digit labels.
+pub fn parse_diagram(lines: &[String]) -> Result<(Stacks, usize)> {
+    let (footer, labels) = lines
+        .iter()
+        .enumerate()
+        .find_map(|(no, line)| footer_labels(line).map(|labels| (no, labels)))
+        .ok_or_else(|| anyhow!("stack diagram has no numbered footer"))?;
+    let mut stacks = vec![vec![]; labels.len()];
+
+    for (no, line) in lines[..footer].iter().enumerate().rev() {
+        let chars = line.chars().collect::<Vec<char>>();
+        let mut filled = vec![false; labels.len()];
+        let mut pos = 0;
+
+        while pos < chars.len() {
+            match chars[pos..] {
+                [' ', ..] => pos += 1,
+                ['[', c, ']', ..] if c != ' ' => {
+                    let stack = stack_under(&labels, pos).ok_or_else(|| {
+                        anyhow!("line {}: crate '{}' isn't above a stack label", no + 1, c)
+                    })?;
+                    if filled[stack] {
+                        bail!("line {}: two crates above stack {}", no + 1, stack + 1);
+                    }
+                    if stacks[stack].len() != footer - no - 1 {
+                        bail!(
+                            "line {}: crate '{}' is floating above stack {}",
+                            no + 1,
+                            c,
+                            stack + 1
+                        );
+                    }
+                    filled[stack] = true;
+                    stacks[stack].push(c);
+                    pos += 3;
+                }
+                _ => bail!(
+                    "line {}: unexpected '{}' at column {}",
+                    no + 1,
+                    chars[pos],
+                    pos + 1
+                ),
+            }
+        }
+    }
+
+    Ok((Stacks::new(stacks), footer + 1))
+}
+
+// Splits the puzzle input into the starting stacks and the moves.
+pub fn parse(lines: &[String]) -> Result<(Stacks, Vec<Move>)> {
+    let (stacks, used) = parse_diagram(lines)?;
+    let moves = parse_moves(&lines[used..], used + 1)?;
+
+    Ok((stacks, moves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_diagram, Bulk, Crane, Move, OneAtATime, Stacks};
+
+    // One string per stack, bottom crate first.
+    fn stacks(stacks: &[&str]) -> Stacks {
+        Stacks::new(stacks.iter().map(|s| s.chars().collect()).collect())
+    }
+
+    fn mv(count: usize, from: usize, to: usize) -> Move {
+        Move { count, from, to }
+    }
+
+    #[test]
+    fn refuses_to_pop_from_an_empty_stack() {
+        let start = stacks(&["ZN", "MCD", ""]);
+        let mut crane = Crane::new(start.clone(), OneAtATime);
+
+        let err = crane.apply(&mv(1, 3, 1)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't move 1 crates from stack 3, it only has 0"
+        );
+        let err = crane.apply(&mv(3, 1, 3)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't move 3 crates from stack 1, it only has 2"
+        );
+        assert!(crane.apply(&mv(1, 4, 1)).is_err());
+        assert!(crane.apply(&mv(1, 0, 1)).is_err());
+        assert_eq!(crane.stacks(), &start);
+        assert!(crane.history().is_empty());
+
+        // A run stops at the first bad move, after the ones before it.
+        let mut crane = Crane::new(start, Bulk);
+        let err = crane.run(&[mv(2, 1, 3), mv(1, 1, 2)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "move 2: can't move 1 crates from stack 1, it only has 0"
+        );
+        assert_eq!(crane.stacks(), &stacks(&["", "MCD", "ZN"]));
+        assert_eq!(crane.position(), 1);
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_owned).collect()
+    }
+
+    const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    #[test]
+    fn parses_the_puzzle_example() {
+        let (start, moves) = parse(&lines(EXAMPLE)).unwrap();
+        assert_eq!(start, stacks(&["ZN", "MCD", "P"]));
+        assert_eq!(moves.len(), 4);
+        assert_eq!(moves[1], mv(3, 1, 3));
+    }
+
+    #[test]
+    fn parses_ragged_lines() {
+        let trimmed = EXAMPLE
+            .lines()
+            .map(|line| line.trim_end().to_owned())
+            .collect::<Vec<_>>();
+        let (start, used) = parse_diagram(&trimmed).unwrap();
+        assert_eq!(start, stacks(&["ZN", "MCD", "P"]));
+        assert_eq!(used, 4);
+
+        // Only the tallest stack reaches the top rows.
+        let (start, _) = parse_diagram(&lines("[A]\n[B]\n[C] [D]\n 1   2   3")).unwrap();
+        assert_eq!(start, stacks(&["CBA", "D", ""]));
+    }
+
+    #[test]
+    fn parses_multi_digit_labels() {
+        let diagram = [
+            "                                    [X]",
+            "[A]                                 [K] [L]",
+            " 1   2   3   4   5   6   7   8   9   10  11  12",
+        ]
+        .join("\n");
+        let (start, used) = parse_diagram(&lines(&diagram)).unwrap();
+        assert_eq!(start.len(), 12);
+        assert_eq!(start.get(1), Some(&['A'][..]));
+        assert_eq!(start.get(10), Some(&['K', 'X'][..]));
+        assert_eq!(start.get(11), Some(&['L'][..]));
+        assert_eq!(start.get(12), Some(&[][..]));
+        assert_eq!(start.tops(), "AXL");
+        assert_eq!(used, 3);
+    }
+
+    #[test]
+    fn renders_diagrams_that_parse_back() {
+        let (start, _) = parse(&lines(EXAMPLE)).unwrap();
+        // Like the old `print_stacks`, every column is padded on the right.
+        let rendered = start.to_string();
+        assert_eq!(
+            rendered.lines().map(str::trim_end).collect::<Vec<_>>(),
+            EXAMPLE
+                .lines()
+                .take(4)
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+        );
+
+        let many = Stacks::new(
+            (0..120)
+                .map(|i| {
+                    (0..i % 4)
+                        .map(|h| (b'A' + (i + h) as u8 % 26) as char)
+                        .collect()
+                })
+                .collect(),
+        );
+        for start in [start, many, stacks(&["", "Q"])] {
+            let (parsed, used) = parse_diagram(&lines(&start.to_string())).unwrap();
+            assert_eq!(parsed, start);
+            assert_eq!(used, start.to_string().lines().count());
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_diagrams() {
+        let cases = [
+            ("[A]\n[B]", "stack diagram has no numbered footer"),
+            ("[A]\n\n 1", "line 1: crate 'A' is floating above stack 1"),
+            ("    [A]\n 1", "line 1: crate 'A' isn't above a stack label"),
+            ("[A] x\n 1", "line 1: unexpected 'x' at column 5"),
+        ];
+        for (diagram, message) in cases {
+            assert_eq!(
+                parse_diagram(&lines(diagram)).unwrap_err().to_string(),
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn undoes_and_redoes_moves() {
+        let (start, moves) = parse(&lines(EXAMPLE)).unwrap();
+        let mut crane = Crane::new(start.clone(), OneAtATime);
+        crane.run(&moves).unwrap();
+        let end = crane.stacks().clone();
+        assert_eq!(end.tops(), "CMZ");
+        assert!(!crane.can_redo());
+        assert!(crane.redo().is_none());
+
+        assert_eq!(crane.undo().unwrap().mv, moves[3]);
+        assert!(crane.can_redo());
+        while crane.undo().is_some() {}
+        assert_eq!(crane.stacks(), &start);
+        assert_eq!(crane.position(), 0);
+        assert!(crane.history().is_empty());
+
+        while crane.redo().is_some() {}
+        assert_eq!(crane.stacks(), &end);
+        assert_eq!(crane.position(), 4);
+
+        // Applying a move after undoing drops the undone steps.
+        crane.undo();
+        crane.undo();
+        crane.apply(&mv(1, 2, 3)).unwrap();
+        assert_eq!(crane.position(), 3);
+        assert!(!crane.can_redo());
+        assert_eq!(crane.history()[2].mv, mv(1, 2, 3));
+        assert_eq!(crane.stacks(), &stacks(&["", "M", "PDNZC"]));
+    }
+
+    #[test]
+    fn seeks_to_any_move() {
+        let (start, moves) = parse(&lines(EXAMPLE)).unwrap();
+        let mut crane = Crane::new(start.clone(), Bulk);
+        crane.run(&moves).unwrap();
+
+        for n in [2, 0, 4, 1, 3, 3] {
+            crane.seek(n).unwrap();
+            let mut fresh = Crane::new(start.clone(), Bulk);
+            fresh.run(&moves[..n]).unwrap();
+            assert_eq!(crane.stacks(), fresh.stacks());
+            assert_eq!(crane.position(), n);
+            assert_eq!(crane.history(), fresh.history());
+        }
+
+        let err = crane.seek(5).unwrap_err();
+        assert_eq!(err.to_string(), "can't go to move 5, only 4 have been made");
+        assert_eq!(crane.position(), 3);
+    }
+
+    #[test]
+    fn logs_which_crates_moved_where() {
+        let (start, moves) = parse(&lines(EXAMPLE)).unwrap();
+
+        let mut crane = Crane::new(start.clone(), OneAtATime);
+        crane.run(&moves).unwrap();
+        assert_eq!(
+            crane.diff_log(),
+            "\
+1: 1 from 2 to 1: D
+2: 3 from 1 to 3: ZND -> DNZ
+3: 2 from 2 to 1: MC -> CM
+4: 1 from 1 to 2: M
+"
+        );
+        crane.seek(2).unwrap();
+        assert_eq!(crane.diff_log().lines().count(), 2);
+
+        let mut crane = Crane::new(start.clone(), Bulk);
+        crane.run(&moves).unwrap();
+        assert_eq!(
+            crane.diff_log(),
+            "\
+1: 1 from 2 to 1: D
+2: 3 from 1 to 3: ZND
+3: 2 from 2 to 1: MC
+4: 1 from 1 to 2: C
+"
+        );
+
+        assert_eq!(Crane::new(start, Bulk).diff_log(), "");
+    }
+}
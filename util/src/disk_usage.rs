@@ -0,0 +1,394 @@
+use serde_json::json;
+
+use crate::filesystem::{Entry, Filesystem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Path,
+    SizeAscending,
+    SizeDescending,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DuOptions {
+    pub max_depth: Option<usize>,
+    pub sort: SortOrder,
+    pub human_readable: bool,
+    pub include_files: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuEntry {
+    pub path: String,
+    pub depth: usize,
+    pub size: usize,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeletionPlan {
+    pub dirs: Vec<String>,
+    pub freed: usize,
+}
+
+fn depth_of(path: &str) -> usize {
+    path.split('/').filter(|c| !c.is_empty()).count()
+}
+
+pub fn report(fs: &Filesystem, options: &DuOptions) -> Vec<DuEntry> {
+    let mut entries = fs
+        .walk()
+        .filter_map(|(path, entry)| {
+            let depth = depth_of(&path);
+            if options.max_depth.map(|max| depth > max).unwrap_or(false) {
+                return None;
+            }
+            match entry {
+                Entry::Directory(dir) => Some(DuEntry {
+                    path,
+                    depth,
+                    size: fs.dir_size(dir),
+                    is_dir: true,
+                }),
+                Entry::File(file) if options.include_files => Some(DuEntry {
+                    path,
+                    depth,
+                    size: file.size(),
+                    is_dir: false,
+                }),
+                Entry::File(_) => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match options.sort {
+        SortOrder::Path => {}
+        SortOrder::SizeAscending => {
+            entries.sort_by(|a, b| a.size.cmp(&b.size).then(a.path.cmp(&b.path)))
+        }
+        SortOrder::SizeDescending => {
+            entries.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)))
+        }
+    }
+
+    entries
+}
+
+pub fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = "";
+    for u in UNITS {
+        size /= 1024.0;
+        unit = u;
+        if size < 1024.0 {
+            break;
+        }
+    }
+
+    if size < 10.0 {
+        format!("{size:.1}{unit}")
+    } else {
+        format!("{size:.0}{unit}")
+    }
+}
+
+pub fn render(entries: &[DuEntry], options: &DuOptions) -> Vec<String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let size = if options.human_readable {
+                human_size(entry.size)
+            } else {
+                entry.size.to_string()
+            };
+            format!("{size}\t{}", entry.path)
+        })
+        .collect()
+}
+
+pub fn to_json(entries: &[DuEntry]) -> String {
+    json!(entries
+        .iter()
+        .map(|entry| json!({
+            "path": entry.path,
+            "depth": entry.depth,
+            "size": entry.size,
+            "type": if entry.is_dir { "dir" } else { "file" },
+        }))
+        .collect::<Vec<_>>())
+    .to_string()
+}
+
+fn is_ancestor(ancestor: &str, path: &str) -> bool {
+    ancestor == "/" || path.starts_with(&format!("{ancestor}/"))
+}
+
+// Picks directories to delete so at least `space_needed` bytes are free. The
+// plan deletes as little data as possible, which can take several
+// directories, and among equally small plans uses the fewest. The root is
+// a candidate too, so a plan can fall back to deleting everything. Nested
+// directories are never both chosen since deleting the parent already frees
+// the child. Finding the best set is a knapsack problem, so on trees much
+// larger than a puzzle input the search gives up after `SEARCH_BUDGET` steps
+// and returns the best plan found so far, which is never worse than deleting
+// the smallest single directory that frees enough.
+const SEARCH_BUDGET: usize = 1_000_000;
+
+pub fn plan_deletion(
+    fs: &Filesystem,
+    total_space: usize,
+    space_needed: usize,
+) -> Option<DeletionPlan> {
+    let used = fs.dir_size(fs.root());
+    let free = total_space.saturating_sub(used);
+    let to_free = space_needed.saturating_sub(free);
+    if to_free == 0 {
+        return Some(DeletionPlan {
+            dirs: vec![],
+            freed: 0,
+        });
+    }
+
+    if used < to_free {
+        return None;
+    }
+
+    let mut candidates = fs
+        .dirs()
+        .map(|(path, dir)| (path, fs.dir_size(dir)))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    // `reachable[i]` is everything from `i` on, counting nested directories
+    // twice, so it bounds what any extension can still free.
+    let mut reachable = vec![0; candidates.len() + 1];
+    for i in (0..candidates.len()).rev() {
+        reachable[i] = reachable[i + 1] + candidates[i].1;
+    }
+
+    let smallest = (0..candidates.len())
+        .filter(|i| candidates[*i].1 >= to_free)
+        .min_by_key(|i| (candidates[*i].1, &candidates[*i].0))?;
+    let mut search = Search {
+        candidates: &candidates,
+        reachable: &reachable,
+        to_free,
+        chosen: vec![],
+        best: Some((candidates[smallest].1, vec![smallest])),
+        budget: SEARCH_BUDGET,
+    };
+    search.run(0, 0);
+
+    search.best.map(|(freed, chosen)| DeletionPlan {
+        freed,
+        dirs: chosen
+            .into_iter()
+            .map(|i| candidates[i].0.clone())
+            .collect(),
+    })
+}
+
+struct Search<'a> {
+    candidates: &'a [(String, usize)],
+    reachable: &'a [usize],
+    to_free: usize,
+    chosen: Vec<usize>,
+    best: Option<(usize, Vec<usize>)>,
+    budget: usize,
+}
+
+impl Search<'_> {
+    // Whether a plan freeing `freed` with `count` directories would beat the
+    // best one so far. Adding directories only makes a plan worse, so this
+    // also prunes every extension of a losing plan.
+    fn improves(&self, freed: usize, count: usize) -> bool {
+        match &self.best {
+            Some((best_freed, best)) => (freed, count) < (*best_freed, best.len()),
+            None => true,
+        }
+    }
+
+    fn run(&mut self, start: usize, freed: usize) {
+        if self.budget == 0 {
+            return;
+        }
+        self.budget -= 1;
+        if freed >= self.to_free {
+            if self.improves(freed, self.chosen.len()) {
+                self.best = Some((freed, self.chosen.clone()));
+            }
+            return;
+        }
+
+        for i in start..self.candidates.len() {
+            if freed + self.reachable[i] < self.to_free {
+                break;
+            }
+            let (path, size) = &self.candidates[i];
+            if !self.improves(freed + size, self.chosen.len() + 1) {
+                continue;
+            }
+            let nested = self.chosen.iter().any(|c| {
+                let other = &self.candidates[*c].0;
+                is_ancestor(other, path) || is_ancestor(path, other)
+            });
+            if nested {
+                continue;
+            }
+
+            self.chosen.push(i);
+            self.run(i + 1, freed + size);
+            self.chosen.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{human_size, plan_deletion, render, report, to_json, DuOptions, SortOrder};
+    use crate::transcript;
+
+    fn load(transcript: &str) -> crate::filesystem::Filesystem {
+        let lines = transcript.lines().map(str::to_owned).collect::<Vec<_>>();
+        transcript::load(&lines).unwrap()
+    }
+
+    // / holds a (40), b (40) and c (5), each with one file, plus a loose
+    // 15 byte file.
+    const THREE_DIRS: &str = "\
+$ cd /
+$ ls
+dir a
+dir b
+dir c
+15 x
+$ cd a
+$ ls
+40 f
+$ cd ../b
+$ ls
+40 f
+$ cd ../c
+$ ls
+5 f";
+
+    #[test]
+    fn deletes_several_directories_when_that_frees_less() {
+        let fs = load(THREE_DIRS);
+        let plan = plan_deletion(&fs, 100, 70).unwrap();
+        assert_eq!(plan.dirs, vec!["/a", "/b"]);
+        assert_eq!(plan.freed, 80);
+
+        let plan = plan_deletion(&fs, 100, 40).unwrap();
+        assert_eq!(plan.dirs, vec!["/a"]);
+        assert_eq!(plan.freed, 40);
+
+        let plan = plan_deletion(&fs, 100, 45).unwrap();
+        assert_eq!(plan.dirs, vec!["/a", "/c"]);
+        assert_eq!(plan.freed, 45);
+    }
+
+    #[test]
+    fn needs_nothing_when_there_is_already_room() {
+        let plan = plan_deletion(&load(THREE_DIRS), 1000, 100).unwrap();
+        assert!(plan.dirs.is_empty());
+        assert_eq!(plan.freed, 0);
+    }
+
+    #[test]
+    fn deletes_the_root_when_nothing_smaller_frees_enough() {
+        let fs = load("$ cd /\n$ ls\n60 a.txt\ndir d\n$ cd d\n$ ls\n30 b.txt");
+        let plan = plan_deletion(&fs, 100, 50).unwrap();
+        assert_eq!(plan.dirs, vec!["/"]);
+        assert_eq!(plan.freed, 90);
+
+        let plan = plan_deletion(&fs, 100, 30).unwrap();
+        assert_eq!(plan.dirs, vec!["/d"]);
+        assert!(plan_deletion(&fs, 100, 200).is_none());
+    }
+
+    #[test]
+    fn reports_sorted_and_limited_by_depth() {
+        let fs = load(THREE_DIRS);
+        let paths = |options: &DuOptions| {
+            report(&fs, options)
+                .into_iter()
+                .map(|e| format!("{} {}", e.size, e.path))
+                .collect::<Vec<_>>()
+        };
+
+        let options = DuOptions::default();
+        assert_eq!(paths(&options), vec!["100 /", "40 /a", "40 /b", "5 /c"]);
+
+        let options = DuOptions {
+            sort: SortOrder::SizeAscending,
+            include_files: true,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&options),
+            vec!["5 /c", "15 /x", "40 /a", "40 /b", "100 /"]
+        );
+
+        let options = DuOptions {
+            sort: SortOrder::SizeDescending,
+            include_files: true,
+            ..Default::default()
+        };
+        assert_eq!(paths(&options)[..3], ["100 /", "40 /a", "40 /a/f"]);
+
+        let options = DuOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(paths(&options), vec!["100 /"]);
+    }
+
+    #[test]
+    fn formats_human_readable_sizes() {
+        assert_eq!(human_size(0), "0");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(20 * 1024), "20K");
+        assert_eq!(human_size(48381165), "46M");
+        assert_eq!(human_size(3 << 30), "3.0G");
+    }
+
+    #[test]
+    fn renders_text_and_json() {
+        let fs = load("$ cd /\n$ ls\ndir d\n$ cd d\n$ ls\n2048 f");
+        let options = DuOptions {
+            include_files: true,
+            ..Default::default()
+        };
+        let entries = report(&fs, &options);
+        assert_eq!(
+            render(&entries, &options),
+            vec!["2048\t/", "2048\t/d", "2048\t/d/f"]
+        );
+
+        let options = DuOptions {
+            human_readable: true,
+            ..options
+        };
+        assert_eq!(render(&entries, &options)[0], "2.0K\t/");
+
+        let json = serde_json::from_str::<serde_json::Value>(&to_json(&entries[1..])).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"path": "/d", "depth": 1, "size": 2048, "type": "dir"},
+                {"path": "/d/f", "depth": 2, "size": 2048, "type": "file"},
+            ])
+        );
+    }
+}
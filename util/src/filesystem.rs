@@ -0,0 +1,536 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Clone, Debug)]
+pub struct Directory {
+    id: usize,
+    parent: usize,
+    name: String,
+    files: HashMap<String, usize>,
+    subdirs: HashMap<String, usize>,
+    size: Cell<Option<usize>>,
+}
+
+impl Directory {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn parent(&self) -> usize {
+        self.parent
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct File {
+    id: usize,
+    parent: usize,
+    name: String,
+    size: usize,
+}
+
+impl File {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn parent(&self) -> usize {
+        self.parent
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Entry<'a> {
+    Directory(&'a Directory),
+    File(&'a File),
+}
+
+impl<'a> Entry<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Entry::Directory(dir) => &dir.name,
+            Entry::File(file) => &file.name,
+        }
+    }
+}
+
+// An entry `Filesystem::from_disk` couldn't read and left out.
+#[derive(Debug)]
+pub struct Warning {
+    pub path: PathBuf,
+    pub error: std::io::Error,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot read '{}': {}", self.path.display(), self.error)
+    }
+}
+
+// Directories and files live in id-indexed vectors; deleting an entry leaves
+// its slot empty so the ids of everything else stay valid.
+#[derive(Debug)]
+pub struct Filesystem {
+    directories: Vec<Option<Directory>>,
+    files: Vec<Option<File>>,
+    pwd: usize,
+}
+
+impl Default for Filesystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filesystem {
+    pub fn new() -> Self {
+        Self {
+            directories: vec![Some(Directory {
+                id: 0,
+                parent: 0,
+                name: "/".into(),
+                files: HashMap::new(),
+                subdirs: HashMap::new(),
+                size: Cell::new(None),
+            })],
+            files: vec![],
+            pwd: 0,
+        }
+    }
+
+    // Builds a filesystem mirroring a directory on disk, using each file's
+    // apparent size. Symlinks and other special files are skipped. Like `du`,
+    // entries that can't be read are left out, and returned as warnings.
+    pub fn from_disk(path: impl AsRef<Path>) -> Result<(Self, Vec<Warning>)> {
+        let path = path.as_ref();
+        if !fs::metadata(path)?.is_dir() {
+            bail!("'{}' is not a directory", path.display());
+        }
+
+        let mut filesystem = Self::new();
+        let mut warnings = vec![];
+        filesystem.import_dir(path, &mut warnings)?;
+        filesystem.pwd = 0;
+
+        Ok((filesystem, warnings))
+    }
+
+    fn import_dir(&mut self, path: &Path, warnings: &mut Vec<Warning>) -> Result<()> {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                warnings.push(Warning {
+                    path: path.to_owned(),
+                    error,
+                });
+                return Ok(());
+            }
+        };
+        let mut entries = entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(error) => {
+                    warnings.push(Warning {
+                        path: path.to_owned(),
+                        error,
+                    });
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let metadata = match fs::symlink_metadata(entry.path()) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    warnings.push(Warning {
+                        path: entry.path(),
+                        error,
+                    });
+                    continue;
+                }
+            };
+            if metadata.is_dir() {
+                self.add_dir(name.clone())?;
+                self.cd(&name)?;
+                self.import_dir(&entry.path(), warnings)?;
+                self.cd("..")?;
+            } else if metadata.is_file() {
+                self.add_file(name, metadata.len() as usize)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn root(&self) -> &Directory {
+        self.directory(0)
+    }
+
+    pub fn current_dir(&self) -> &Directory {
+        self.directory(self.pwd)
+    }
+
+    fn directory(&self, id: usize) -> &Directory {
+        self.directories[id]
+            .as_ref()
+            .expect("directory was deleted")
+    }
+
+    fn directory_mut(&mut self, id: usize) -> &mut Directory {
+        self.directories[id]
+            .as_mut()
+            .expect("directory was deleted")
+    }
+
+    fn file(&self, id: usize) -> &File {
+        self.files[id].as_ref().expect("file was deleted")
+    }
+
+    pub fn ls_dir<'a>(&'a self, dir: &'a Directory) -> Vec<Entry<'a>> {
+        let mut entries = dir
+            .subdirs
+            .values()
+            .map(|id| Entry::Directory(self.directory(*id)))
+            .collect::<Vec<_>>();
+        entries.extend(dir.files.values().map(|id| Entry::File(self.file(*id))));
+
+        entries
+    }
+
+    pub fn cd(&mut self, path: &str) -> Result<()> {
+        let mut pwd = if path.starts_with('/') { 0 } else { self.pwd };
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            let dir = self.directory(pwd);
+            pwd = match component {
+                "." => pwd,
+                ".." => dir.parent,
+                name => *dir
+                    .subdirs
+                    .get(name)
+                    .ok_or_else(|| anyhow!("No such directory: {}", path))?,
+            };
+        }
+        self.pwd = pwd;
+
+        Ok(())
+    }
+
+    pub fn child<'a>(&'a self, dir: &'a Directory, name: &str) -> Option<Entry<'a>> {
+        if let Some(id) = dir.subdirs.get(name) {
+            return Some(Entry::Directory(self.directory(*id)));
+        }
+
+        dir.files.get(name).map(|id| Entry::File(self.file(*id)))
+    }
+
+    // Names are unique within a directory, whether they belong to a file or
+    // a subdirectory.
+    fn check_new_name(&self, name: &str) -> Result<()> {
+        let pwd = self.current_dir();
+        if self.child(pwd, name).is_some() {
+            bail!("'{}' already exists in {}", name, self.path_of(pwd));
+        }
+
+        Ok(())
+    }
+
+    pub fn add_file(&mut self, name: String, size: usize) -> Result<usize> {
+        self.check_new_name(&name)?;
+        let id = self.files.len();
+        self.files.push(Some(File {
+            id,
+            parent: self.pwd,
+            name: name.clone(),
+            size,
+        }));
+
+        self.directory_mut(self.pwd).files.insert(name, id);
+        self.invalidate_sizes(self.pwd);
+
+        Ok(id)
+    }
+
+    pub fn add_dir(&mut self, name: String) -> Result<usize> {
+        self.check_new_name(&name)?;
+        let id = self.directories.len();
+        self.directories.push(Some(Directory {
+            id,
+            parent: self.pwd,
+            name: name.clone(),
+            files: HashMap::new(),
+            subdirs: HashMap::new(),
+            size: Cell::new(None),
+        }));
+
+        self.directory_mut(self.pwd).subdirs.insert(name, id);
+
+        Ok(id)
+    }
+
+    pub fn lookup(&self, path: &str) -> Option<Entry<'_>> {
+        let mut dir = self.root();
+        let mut components = path.split('/').filter(|c| !c.is_empty()).peekable();
+
+        while let Some(component) = components.next() {
+            if let Some(id) = dir.subdirs.get(component) {
+                dir = self.directory(*id);
+            } else if components.peek().is_none() {
+                return dir
+                    .files
+                    .get(component)
+                    .map(|id| Entry::File(self.file(*id)));
+            } else {
+                return None;
+            }
+        }
+
+        Some(Entry::Directory(dir))
+    }
+
+    pub fn dir(&self, path: &str) -> Option<&Directory> {
+        match self.lookup(path)? {
+            Entry::Directory(dir) => Some(dir),
+            Entry::File(_) => None,
+        }
+    }
+
+    pub fn path_of(&self, dir: &Directory) -> String {
+        let mut names = vec![];
+        let mut current = dir;
+        while current.id != 0 {
+            names.push(current.name.as_str());
+            current = self.directory(current.parent);
+        }
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    pub fn dir_size(&self, dir: &Directory) -> usize {
+        if let Some(size) = dir.size.get() {
+            return size;
+        }
+
+        let size = dir
+            .files
+            .values()
+            .map(|id| self.file(*id).size)
+            .sum::<usize>()
+            + dir
+                .subdirs
+                .values()
+                .map(|id| self.dir_size(self.directory(*id)))
+                .sum::<usize>();
+        dir.size.set(Some(size));
+
+        size
+    }
+
+    pub fn dir_sizes(&self) -> HashMap<String, usize> {
+        self.dirs()
+            .map(|(path, dir)| (path, self.dir_size(dir)))
+            .collect()
+    }
+
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            fs: self,
+            stack: vec![("/".into(), Entry::Directory(self.root()))],
+        }
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = (String, &Directory)> {
+        self.walk().filter_map(|(path, entry)| match entry {
+            Entry::Directory(dir) => Some((path, dir)),
+            Entry::File(_) => None,
+        })
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (String, &File)> {
+        self.walk().filter_map(|(path, entry)| match entry {
+            Entry::File(file) => Some((path, file)),
+            Entry::Directory(_) => None,
+        })
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<()> {
+        let (parent, name, dir_id) = match self
+            .lookup(path)
+            .ok_or_else(|| anyhow!("No such file or directory: {}", path))?
+        {
+            Entry::Directory(dir) if dir.id == 0 => bail!("Can't remove the root directory"),
+            Entry::Directory(dir) => (dir.parent, dir.name.clone(), Some(dir.id)),
+            Entry::File(file) => (file.parent, file.name.clone(), None),
+        };
+
+        let parent_dir = self.directory_mut(parent);
+        match dir_id {
+            Some(id) => {
+                parent_dir.subdirs.remove(&name);
+                self.delete_tree(id);
+            }
+            None => {
+                if let Some(id) = parent_dir.files.remove(&name) {
+                    self.files[id] = None;
+                }
+            }
+        }
+        self.invalidate_sizes(parent);
+
+        Ok(())
+    }
+
+    fn delete_tree(&mut self, id: usize) {
+        if let Some(dir) = self.directories[id].take() {
+            for file in dir.files.values() {
+                self.files[*file] = None;
+            }
+            for subdir in dir.subdirs.values() {
+                self.delete_tree(*subdir);
+            }
+            if self.pwd == id {
+                self.pwd = 0;
+            }
+        }
+    }
+
+    fn invalidate_sizes(&self, id: usize) {
+        let mut current = id;
+        loop {
+            let dir = self.directory(current);
+            dir.size.set(None);
+            if current == 0 {
+                break;
+            }
+            current = dir.parent;
+        }
+    }
+}
+
+// Depth-first walk over the tree, visiting entries in name order and
+// yielding each one with its full path.
+pub struct Walk<'a> {
+    fs: &'a Filesystem,
+    stack: Vec<(String, Entry<'a>)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, Entry<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, entry) = self.stack.pop()?;
+
+        if let Entry::Directory(dir) = entry {
+            let mut children = self.fs.ls_dir(dir);
+            children.sort_by(|a, b| b.name().cmp(a.name()));
+            let prefix = if dir.id == 0 { "" } else { path.as_str() };
+            self.stack.extend(
+                children
+                    .into_iter()
+                    .map(|child| (format!("{}/{}", prefix, child.name()), child)),
+            );
+        }
+
+        Some((path, entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, Filesystem};
+
+    // /a/b.txt (10), /a/c/d (20), /e (5)
+    fn example() -> Filesystem {
+        let mut fs = Filesystem::new();
+        fs.add_dir("a".into()).unwrap();
+        fs.add_file("e".into(), 5).unwrap();
+        fs.cd("a").unwrap();
+        fs.add_file("b.txt".into(), 10).unwrap();
+        fs.add_dir("c".into()).unwrap();
+        fs.cd("c").unwrap();
+        fs.add_file("d".into(), 20).unwrap();
+        fs.cd("/").unwrap();
+
+        fs
+    }
+
+    #[test]
+    fn rejects_names_already_in_the_directory() {
+        let mut fs = example();
+        let error = fs.add_dir("e".into()).unwrap_err().to_string();
+        assert_eq!(error, "'e' already exists in /");
+        fs.cd("a").unwrap();
+        assert!(fs.add_file("c".into(), 1).is_err());
+        assert!(fs.add_file("b.txt".into(), 1).is_err());
+        assert_eq!(fs.dir_sizes()["/"], 35);
+    }
+
+    #[test]
+    fn looks_up_paths() {
+        let fs = example();
+        assert!(matches!(fs.lookup("/"), Some(Entry::Directory(d)) if d.id() == 0));
+        assert!(matches!(fs.lookup("/a/c/d"), Some(Entry::File(f)) if f.size() == 20));
+        assert!(matches!(fs.lookup("a/b.txt"), Some(Entry::File(f)) if f.size() == 10));
+        assert!(fs.lookup("/a/b.txt/x").is_none());
+        assert!(fs.lookup("/missing").is_none());
+        assert!(fs.dir("/e").is_none());
+        assert_eq!(fs.path_of(fs.dir("/a/c").unwrap()), "/a/c");
+    }
+
+    #[test]
+    fn walks_in_name_order() {
+        let fs = example();
+        let paths = fs.walk().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/", "/a", "/a/b.txt", "/a/c", "/a/c/d", "/e"]);
+    }
+
+    #[test]
+    fn removes_entries_and_updates_cached_sizes() {
+        let mut fs = example();
+        assert_eq!(fs.dir_sizes()["/a"], 30);
+
+        fs.remove("/a/c").unwrap();
+        assert!(fs.lookup("/a/c/d").is_none());
+        assert_eq!(fs.dir_sizes()["/a"], 10);
+        assert_eq!(fs.dir_sizes()["/"], 15);
+
+        fs.cd("/a").unwrap();
+        fs.add_file("f".into(), 7).unwrap();
+        assert_eq!(fs.dir_sizes()["/"], 22);
+
+        fs.remove("/e").unwrap();
+        assert_eq!(fs.dir_sizes()["/"], 17);
+        assert!(fs.remove("/e").is_err());
+        assert!(fs.remove("/").is_err());
+    }
+
+    #[test]
+    fn moves_back_to_the_root_when_the_current_directory_is_removed() {
+        let mut fs = example();
+        fs.cd("/a/c").unwrap();
+        fs.remove("/a").unwrap();
+        assert_eq!(fs.current_dir().id(), 0);
+        assert_eq!(fs.dir_sizes().len(), 1);
+    }
+}
@@ -0,0 +1,657 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use anyhow::{bail, Result};
+
+// Grid coordinates, with y growing downwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+// The offset between two points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Vec2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point2 {
+    pub const ORIGIN: Point2 = Point2 { x: 0, y: 0 };
+
+    pub fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(self, other: Point2) -> isize {
+        (other - self).manhattan()
+    }
+
+    pub fn chebyshev(self, other: Point2) -> isize {
+        (other - self).chebyshev()
+    }
+
+    pub fn neighbors(self) -> impl Iterator<Item = Point2> {
+        Direction::ALL.into_iter().map(move |dir| self + dir)
+    }
+}
+
+impl Vec2 {
+    pub fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn scale(self, factor: isize) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+
+    pub fn manhattan(self) -> isize {
+        self.x.abs() + self.y.abs()
+    }
+
+    pub fn chebyshev(self) -> isize {
+        self.x.abs().max(self.y.abs())
+    }
+
+    // One step along each axis towards the offset, or none along an axis
+    // it doesn't move on.
+    pub fn signum(self) -> Self {
+        Self::new(self.x.signum(), self.y.signum())
+    }
+}
+
+impl fmt::Display for Point2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}, {}>", self.x, self.y)
+    }
+}
+
+impl Add<Vec2> for Point2 {
+    type Output = Point2;
+
+    fn add(self, rhs: Vec2) -> Point2 {
+        Point2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Add<Direction> for Point2 {
+    type Output = Point2;
+
+    fn add(self, rhs: Direction) -> Point2 {
+        self + rhs.delta()
+    }
+}
+
+impl AddAssign<Vec2> for Point2 {
+    fn add_assign(&mut self, rhs: Vec2) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Vec2> for Point2 {
+    type Output = Point2;
+
+    fn sub(self, rhs: Vec2) -> Point2 {
+        self + -rhs
+    }
+}
+
+impl SubAssign<Vec2> for Point2 {
+    fn sub_assign(&mut self, rhs: Vec2) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Point2) -> Vec2 {
+        Vec2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, rhs: Vec2) -> Vec2 {
+        Vec2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Vec2) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Vec2) -> Vec2 {
+        self + -rhs
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, rhs: Vec2) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl Sum for Vec2 {
+    fn sum<I: Iterator<Item = Vec2>>(iter: I) -> Vec2 {
+        iter.fold(Vec2::default(), |sum, v| sum + v)
+    }
+}
+
+impl Mul<isize> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, rhs: isize) -> Vec2 {
+        self.scale(rhs)
+    }
+}
+
+impl From<(isize, isize)> for Point2 {
+    fn from((x, y): (isize, isize)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Point2> for (isize, isize) {
+    fn from(point: Point2) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl From<[isize; 2]> for Point2 {
+    fn from([x, y]: [isize; 2]) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Point2> for [isize; 2] {
+    fn from(point: Point2) -> Self {
+        [point.x, point.y]
+    }
+}
+
+impl From<(isize, isize)> for Vec2 {
+    fn from((x, y): (isize, isize)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Vec2> for (isize, isize) {
+    fn from(vec: Vec2) -> Self {
+        (vec.x, vec.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn delta(self) -> Vec2 {
+        match self {
+            Direction::Up => Vec2::new(0, -1),
+            Direction::Down => Vec2::new(0, 1),
+            Direction::Left => Vec2::new(-1, 0),
+            Direction::Right => Vec2::new(1, 0),
+        }
+    }
+
+    // Turns a quarter turn clockwise as seen on screen.
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.turn_right().opposite()
+    }
+
+    pub fn opposite(self) -> Self {
+        self.turn_right().turn_right()
+    }
+}
+
+impl From<Direction> for Vec2 {
+    fn from(direction: Direction) -> Self {
+        direction.delta()
+    }
+}
+
+impl TryFrom<char> for Direction {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> Result<Self> {
+        Ok(match c {
+            'U' | '^' => Direction::Up,
+            'D' | 'v' => Direction::Down,
+            'L' | '<' => Direction::Left,
+            'R' | '>' => Direction::Right,
+            _ => bail!("unknown direction '{}'", c),
+        })
+    }
+}
+
+impl FromStr for Direction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Direction::try_from(c),
+            _ => bail!("unknown direction '{}'", s),
+        }
+    }
+}
+
+// Rotating by 45 degrees turns Manhattan diamonds into axis-aligned squares:
+// u = x + y, v = x - y. Only points where u and v share parity map back onto
+// the integer grid. Rotated points keep u in `x` and v in `y`.
+pub fn rotate(point: Point2) -> Point2 {
+    Point2::new(point.x + point.y, point.x - point.y)
+}
+
+pub fn unrotate(rotated: Point2) -> Option<Point2> {
+    let Point2 { x: u, y: v } = rotated;
+    if (u + v) % 2 != 0 {
+        return None;
+    }
+
+    Some(Point2::new((u + v) / 2, (u - v) / 2))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Diamond {
+    pub center: Point2,
+    pub radius: isize,
+}
+
+// One side of a diamond in rotated space: either u or v is fixed, and the
+// other coordinate runs over an inclusive range.
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    U(isize, (isize, isize)),
+    V(isize, (isize, isize)),
+}
+
+impl Diamond {
+    pub fn new(center: Point2, radius: isize) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn through(center: Point2, edge: Point2) -> Self {
+        Self::new(center, center.manhattan(edge))
+    }
+
+    pub fn contains(&self, point: Point2) -> bool {
+        self.center.manhattan(point) <= self.radius
+    }
+
+    pub fn on_boundary(&self, point: Point2) -> bool {
+        self.center.manhattan(point) == self.radius
+    }
+
+    pub fn rotated_bounds(&self) -> (Point2, Point2) {
+        let rotated = rotate(self.center);
+        let r = self.radius;
+        (
+            Point2::new(rotated.x - r, rotated.y - r),
+            Point2::new(rotated.x + r, rotated.y + r),
+        )
+    }
+
+    pub fn row_span(&self, y: isize) -> Option<(isize, isize)> {
+        let reach = self.radius - (self.center.y - y).abs();
+        if reach < 0 {
+            return None;
+        }
+
+        Some((self.center.x - reach, self.center.x + reach))
+    }
+
+    pub fn column_span(&self, x: isize) -> Option<(isize, isize)> {
+        let reach = self.radius - (self.center.x - x).abs();
+        if reach < 0 {
+            return None;
+        }
+
+        Some((self.center.y - reach, self.center.y + reach))
+    }
+
+    fn sides(&self) -> [Side; 4] {
+        let (Point2 { x: u0, y: v0 }, Point2 { x: u1, y: v1 }) = self.rotated_bounds();
+        [
+            Side::U(u0, (v0, v1)),
+            Side::U(u1, (v0, v1)),
+            Side::V(v0, (u0, u1)),
+            Side::V(v1, (u0, u1)),
+        ]
+    }
+
+    // Lattice points lying on both boundaries. Where two sides overlap along
+    // a line, the ends of the shared segment are returned.
+    pub fn boundary_intersections(&self, other: &Diamond) -> Vec<Point2> {
+        let mut points = BTreeSet::new();
+
+        for side in self.sides() {
+            for other_side in other.sides() {
+                let rotated = match (side, other_side) {
+                    (Side::U(u, vs), Side::V(v, us)) | (Side::V(v, us), Side::U(u, vs)) => {
+                        if in_range(u, us) && in_range(v, vs) {
+                            vec![Point2::new(u, v)]
+                        } else {
+                            vec![]
+                        }
+                    }
+                    (Side::U(u, a), Side::U(other_u, b)) if u == other_u => overlap(a, b)
+                        .map(|(lo, hi)| vec![Point2::new(u, lo), Point2::new(u, hi)])
+                        .unwrap_or_default(),
+                    (Side::V(v, a), Side::V(other_v, b)) if v == other_v => overlap(a, b)
+                        .map(|(lo, hi)| vec![Point2::new(lo, v), Point2::new(hi, v)])
+                        .unwrap_or_default(),
+                    _ => vec![],
+                };
+                points.extend(
+                    rotated
+                        .into_iter()
+                        .flat_map(lattice_near)
+                        .filter(|p| self.on_boundary(*p) && other.on_boundary(*p)),
+                );
+            }
+        }
+
+        points.into_iter().collect()
+    }
+}
+
+pub fn covered(diamonds: &[Diamond], point: Point2) -> bool {
+    diamonds.iter().any(|d| d.contains(point))
+}
+
+// Finds a point inside the inclusive rectangle that no diamond covers. The
+// uncovered point with the smallest u (then smallest v) is always pinned
+// between cells that some diamonds or the rectangle's edges cut off, which
+// puts it on the boundary of a diamond grown by one or two steps. So only
+// the points where two grown boundaries meet, or where one crosses the
+// rectangle's edges, need checking, and only diamonds close enough for
+// their grown boundaries to touch are paired up.
+pub fn find_uncovered(diamonds: &[Diamond], rect: (Point2, Point2)) -> Option<Point2> {
+    let (min, max) = rect;
+    if min.x > max.x || min.y > max.y {
+        return None;
+    }
+
+    let grown = diamonds
+        .iter()
+        .flat_map(|d| [1, 2].map(|step| Diamond::new(d.center, d.radius + step)))
+        .collect::<Vec<_>>();
+
+    let mut candidates = vec![
+        min,
+        Point2::new(min.x, max.y),
+        Point2::new(max.x, min.y),
+        max,
+    ];
+    for diamond in grown.iter() {
+        for y in [min.y, max.y] {
+            if let Some((left, right)) = diamond.row_span(y) {
+                candidates.extend([Point2::new(left, y), Point2::new(right, y)]);
+            }
+        }
+        for x in [min.x, max.x] {
+            if let Some((top, bottom)) = diamond.column_span(x) {
+                candidates.extend([Point2::new(x, top), Point2::new(x, bottom)]);
+            }
+        }
+    }
+    for (idx, diamond) in grown.iter().enumerate() {
+        for other in grown[idx..].iter() {
+            if diamond.center.manhattan(other.center) <= diamond.radius + other.radius {
+                candidates.extend(diamond.boundary_intersections(other));
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|p| p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y)
+        .filter(|p| !covered(diamonds, *p))
+        .min_by_key(|p| (rotate(*p), *p))
+}
+
+fn in_range(value: isize, range: (isize, isize)) -> bool {
+    value >= range.0 && value <= range.1
+}
+
+fn overlap(a: (isize, isize), b: (isize, isize)) -> Option<(isize, isize)> {
+    let lo = a.0.max(b.0);
+    let hi = a.1.min(b.1);
+    (lo <= hi).then_some((lo, hi))
+}
+
+// A rotated point with mismatched parity sits between lattice points; the
+// lattice points on the same line are one step along it in either direction.
+fn lattice_near(rotated: Point2) -> Vec<Point2> {
+    match unrotate(rotated) {
+        Some(point) => vec![point],
+        None => rotated.neighbors().filter_map(unrotate).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{covered, find_uncovered, rotate, unrotate, Diamond, Direction, Point2, Vec2};
+    use crate::grid_n::SparseGridN;
+
+    const EXAMPLE: &[(isize, isize, isize, isize)] = &[
+        (2, 18, -2, 15),
+        (9, 16, 10, 16),
+        (13, 2, 15, 3),
+        (12, 14, 10, 16),
+        (10, 20, 10, 16),
+        (14, 17, 10, 16),
+        (8, 7, 2, 10),
+        (2, 0, 2, 10),
+        (0, 11, 2, 10),
+        (20, 14, 25, 17),
+        (17, 20, 21, 22),
+        (16, 7, 15, 3),
+        (14, 3, 15, 3),
+        (20, 1, 15, 3),
+    ];
+
+    fn brute_force(diamonds: &[Diamond], (min, max): (Point2, Point2)) -> Option<Point2> {
+        let mut points = vec![];
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                points.push(Point2::new(x, y));
+            }
+        }
+
+        points
+            .into_iter()
+            .filter(|p| !covered(diamonds, *p))
+            .min_by_key(|p| (p.x + p.y, p.x - p.y, *p))
+    }
+
+    #[test]
+    fn finds_the_distress_beacon_in_the_example() {
+        let diamonds = EXAMPLE
+            .iter()
+            .map(|&(sx, sy, bx, by)| Diamond::through(Point2::new(sx, sy), Point2::new(bx, by)))
+            .collect::<Vec<_>>();
+        let rect = (Point2::ORIGIN, Point2::new(20, 20));
+        assert_eq!(find_uncovered(&diamonds, rect), Some(Point2::new(14, 11)));
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut state = 15u64;
+        let mut next = |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % bound) as isize
+        };
+
+        for _ in 0..500 {
+            let diamonds = (0..next(8))
+                .map(|_| Diamond::new(Point2::new(next(30) - 5, next(30) - 5), next(9)))
+                .collect::<Vec<_>>();
+            let min = Point2::new(next(10), next(10));
+            let rect = (min, Point2::new(min.x + next(15), min.y + next(15)));
+            assert_eq!(
+                find_uncovered(&diamonds, rect),
+                brute_force(&diamonds, rect),
+                "{diamonds:?} in {rect:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn intersects_boundaries() {
+        let a = Diamond::new(Point2::new(8, 7), 9);
+        let b = Diamond::new(Point2::new(9, 16), 1);
+        assert_eq!(
+            a.boundary_intersections(&b),
+            vec![Point2::new(8, 16), Point2::new(9, 15)]
+        );
+        assert!(a
+            .boundary_intersections(&Diamond::new(Point2::new(40, 40), 1))
+            .is_empty());
+    }
+
+    #[test]
+    fn adds_and_subtracts_points_and_offsets() {
+        let p = Point2::new(3, -2);
+        let v = Vec2::new(-1, 4);
+        assert_eq!(p + v, Point2::new(2, 2));
+        assert_eq!(p - v, Point2::new(4, -6));
+        assert_eq!((p + v) - p, v);
+        assert_eq!(v + v - v, v);
+        assert_eq!(-v, Vec2::new(1, -4));
+        assert_eq!(v * 3, v.scale(3));
+        assert_eq!(v * 3, Vec2::new(-3, 12));
+        assert_eq!([v, v, -v].into_iter().sum::<Vec2>(), v);
+
+        let mut q = p;
+        q += v;
+        q -= v * 2;
+        assert_eq!(q, p - v);
+
+        assert_eq!(v.manhattan(), 5);
+        assert_eq!(v.chebyshev(), 4);
+        assert_eq!(p.manhattan(Point2::ORIGIN), 5);
+        assert_eq!(p.chebyshev(Point2::ORIGIN), 3);
+        assert_eq!(Vec2::new(-7, 0).signum(), Vec2::new(-1, 0));
+        assert_eq!(p.to_string(), "(3, -2)");
+        assert_eq!(v.to_string(), "<-1, 4>");
+    }
+
+    #[test]
+    fn converts_points_to_tuples_and_arrays() {
+        let p = Point2::new(3, -2);
+        assert_eq!(<(isize, isize)>::from(p), (3, -2));
+        assert_eq!(Point2::from((3, -2)), p);
+        assert_eq!(<[isize; 2]>::from(p), [3, -2]);
+        assert_eq!(Point2::from([3, -2]), p);
+
+        let mut grid = SparseGridN::<char, 2>::new();
+        grid.set(p.into(), '#');
+        assert_eq!(grid.get([3, -2]), Some(&'#'));
+        let (min, _) = grid.get_bounds();
+        assert_eq!(Point2::from(min), p);
+    }
+
+    #[test]
+    fn turns_and_finds_neighbors() {
+        for dir in Direction::ALL {
+            assert_eq!(dir.turn_right().turn_left(), dir);
+            assert_eq!(dir.opposite().opposite(), dir);
+            assert_eq!(dir.opposite().delta(), -dir.delta());
+            assert_eq!(dir.turn_right().turn_right(), dir.opposite());
+        }
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Left.turn_right(), Direction::Up);
+
+        // ALL goes clockwise on screen, where y grows downwards.
+        let p = Point2::new(5, 5);
+        assert_eq!(
+            p.neighbors().collect::<Vec<_>>(),
+            [(5, 4), (6, 5), (5, 6), (4, 5)].map(Point2::from)
+        );
+    }
+
+    #[test]
+    fn parses_directions() {
+        for (s, dir) in [
+            ("U", Direction::Up),
+            ("^", Direction::Up),
+            ("D", Direction::Down),
+            ("v", Direction::Down),
+            ("L", Direction::Left),
+            ("<", Direction::Left),
+            ("R", Direction::Right),
+            (">", Direction::Right),
+        ] {
+            assert_eq!(s.parse::<Direction>().unwrap(), dir);
+        }
+        for bad in ["", "u", "UR", "X"] {
+            assert_eq!(
+                bad.parse::<Direction>().unwrap_err().to_string(),
+                format!("unknown direction '{bad}'")
+            );
+        }
+        assert_eq!(Vec2::from(Direction::Down), Vec2::new(0, 1));
+    }
+
+    #[test]
+    fn rotates_and_unrotates() {
+        for p in [(0, 0), (3, -2), (-4, 7)].map(Point2::from) {
+            assert_eq!(unrotate(rotate(p)), Some(p));
+        }
+        assert_eq!(rotate(Point2::new(3, -2)), Point2::new(1, 5));
+        assert_eq!(unrotate(Point2::new(1, 2)), None);
+    }
+}
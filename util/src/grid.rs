@@ -1,22 +1,24 @@
 use std::collections::HashMap;
 
+use crate::geom::Point2;
+
 pub trait Grid<T>
 where
     T: core::fmt::Debug,
 {
-    fn get(&self, location: (isize, isize)) -> Option<&T>;
+    fn get(&self, location: Point2) -> Option<&T>;
 
-    fn set(&mut self, location: (isize, isize), value: T);
+    fn set(&mut self, location: Point2, value: T);
 
-    fn draw(&self, window: ((isize, isize), (isize, isize))) -> Vec<String>;
+    fn draw(&self, window: (Point2, Point2)) -> Vec<String>;
 }
 
 pub struct SparseGrid<T>
 where
     T: core::fmt::Debug + Default,
 {
-    bounds: ((isize, isize), (isize, isize)),
-    data: HashMap<(isize, isize), T>,
+    bounds: (Point2, Point2),
+    data: HashMap<Point2, T>,
     renderer: Box<dyn Fn(&T) -> char>,
 }
 
@@ -26,50 +28,106 @@ where
 {
     pub fn new(renderer: Box<dyn Fn(&T) -> char>) -> Self {
         Self {
-            bounds: ((0, 0), (0, 0)),
+            bounds: (Point2::ORIGIN, Point2::ORIGIN),
             data: HashMap::new(),
             renderer,
         }
     }
 
-    pub fn get_bounds(&self) -> ((isize, isize), (isize, isize)) {
+    pub fn get_bounds(&self) -> (Point2, Point2) {
         self.bounds
     }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn remove(&mut self, location: Point2) -> Option<T> {
+        let removed = self.data.remove(&location)?;
+        let (min, max) = self.bounds;
+        if location.x == min.x || location.x == max.x || location.y == min.y || location.y == max.y
+        {
+            self.recompute_bounds();
+        }
+
+        Some(removed)
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&Point2, &mut T) -> bool) {
+        self.data.retain(f);
+        self.recompute_bounds();
+    }
+
+    pub fn recompute_bounds(&mut self) {
+        let mut locations = self.data.keys();
+        let Some(first) = locations.next() else {
+            self.bounds = (Point2::ORIGIN, Point2::ORIGIN);
+            return;
+        };
+        self.bounds = locations.fold((*first, *first), |bounds, location| {
+            extend_bounds(bounds, *location)
+        });
+    }
+
+    pub fn row_range(&self, y: isize) -> Option<(isize, isize)> {
+        axis_range(self.data.keys().filter(|l| l.y == y).map(|l| l.x))
+    }
+
+    pub fn column_range(&self, x: isize) -> Option<(isize, isize)> {
+        axis_range(self.data.keys().filter(|l| l.x == x).map(|l| l.y))
+    }
+
+    // Draws the window as bare rows, without the axis labels `draw` adds.
+    pub fn render(&self, window: (Point2, Point2)) -> Vec<String> {
+        let default = T::default();
+
+        (window.0.y..=window.1.y)
+            .map(|y| {
+                (window.0.x..=window.1.x)
+                    .map(|x| (self.renderer)(self.data.get(&Point2::new(x, y)).unwrap_or(&default)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn count_where(&self, f: impl Fn(&Point2, &T) -> bool) -> usize {
+        self.data.iter().filter(|(l, v)| f(l, v)).count()
+    }
 }
 
 impl<T> Grid<T> for SparseGrid<T>
 where
     T: core::fmt::Debug + Default,
 {
-    fn get(&self, location: (isize, isize)) -> Option<&T> {
+    fn get(&self, location: Point2) -> Option<&T> {
         self.data.get(&location)
     }
 
-    fn set(&mut self, location: (isize, isize), value: T) {
+    fn set(&mut self, location: Point2, value: T) {
         if self.data.is_empty() {
             self.bounds = (location, location);
         } else {
-            self.bounds.0 .0 = self.bounds.0 .0.min(location.0);
-            self.bounds.0 .1 = self.bounds.0 .1.min(location.1);
-            self.bounds.1 .0 = self.bounds.1 .0.max(location.0);
-            self.bounds.1 .1 = self.bounds.1 .1.max(location.1);
+            self.bounds = extend_bounds(self.bounds, location);
         }
         self.data.insert(location, value);
     }
 
-    fn draw(&self, window: ((isize, isize), (isize, isize))) -> Vec<String> {
+    fn draw(&self, window: (Point2, Point2)) -> Vec<String> {
         let lower_bound = window.0;
         let upper_bound = window.1;
-        let default = T::default();
         let mut result = vec![];
 
         let (pad, lower_x, upper_x) = clamp_x_axis(window);
         let x_num_len = lower_x.to_string().len().max(upper_x.to_string().len());
         let y_num_len = lower_bound
-            .1
+            .y
             .to_string()
             .len()
-            .max(upper_bound.1.to_string().len());
+            .max(upper_bound.y.to_string().len());
         let xl = (lower_x..=upper_x)
             .step_by(5)
             .map(|l| format!("{l: >x_num_len$}").chars().collect::<Vec<_>>())
@@ -86,26 +144,28 @@ where
             ));
         }
         result.push("".to_owned());
-        for y in lower_bound.1..=upper_bound.1 {
-            let mut line = vec![];
-            for x in lower_bound.0..=upper_bound.0 {
-                let v = self.data.get(&(x, y)).unwrap_or(&default);
-                let c = (self.renderer)(v);
-                line.push(c);
-            }
-            result.push(format!(
-                "{: >y_num_len$} {}",
-                y,
-                line.into_iter().collect::<String>()
-            ));
+        for (y, line) in (lower_bound.y..=upper_bound.y).zip(self.render(window)) {
+            result.push(format!("{y: >y_num_len$} {line}"));
         }
 
         result
     }
 }
 
-fn clamp_x_axis(window: ((isize, isize), (isize, isize))) -> (usize, isize, isize) {
-    let mut lb = window.0 .0;
+fn extend_bounds((min, max): (Point2, Point2), location: Point2) -> (Point2, Point2) {
+    (
+        Point2::new(min.x.min(location.x), min.y.min(location.y)),
+        Point2::new(max.x.max(location.x), max.y.max(location.y)),
+    )
+}
+
+fn axis_range(mut coords: impl Iterator<Item = isize>) -> Option<(isize, isize)> {
+    let first = coords.next()?;
+    Some(coords.fold((first, first), |(min, max), c| (min.min(c), max.max(c))))
+}
+
+fn clamp_x_axis(window: (Point2, Point2)) -> (usize, isize, isize) {
+    let mut lb = window.0.x;
     let mut pad = 0;
 
     while lb % 5 != 0 {
@@ -113,5 +173,71 @@ fn clamp_x_axis(window: ((isize, isize), (isize, isize))) -> (usize, isize, isiz
         pad += 1;
     }
 
-    (pad as usize, lb, window.1 .0)
+    (pad as usize, lb, window.1.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, SparseGrid};
+    use crate::geom::Point2;
+
+    fn grid(cells: &[(isize, isize, u8)]) -> SparseGrid<u8> {
+        let mut grid = SparseGrid::new(Box::new(|v| (b'0' + v) as char));
+        for &(x, y, v) in cells {
+            grid.set(Point2::new(x, y), v);
+        }
+
+        grid
+    }
+
+    #[test]
+    fn keeps_default_values_until_removed() {
+        let mut grid = grid(&[(0, 0, 1), (5, -2, 0)]);
+        assert_eq!(grid.get(Point2::new(5, -2)), Some(&0));
+        assert_eq!(grid.get_bounds(), (Point2::new(0, -2), Point2::new(5, 0)));
+
+        assert_eq!(grid.remove(Point2::new(5, -2)), Some(0));
+        assert_eq!(grid.remove(Point2::new(5, -2)), None);
+        assert_eq!(grid.get_bounds(), (Point2::ORIGIN, Point2::ORIGIN));
+        assert_eq!(grid.len(), 1);
+    }
+
+    #[test]
+    fn shrinks_bounds_after_removing_edge_cells() {
+        let mut grid = grid(&[(-3, 1, 1), (0, 0, 2), (4, 7, 3), (2, 2, 4)]);
+        grid.remove(Point2::new(2, 2));
+        assert_eq!(grid.get_bounds(), (Point2::new(-3, 0), Point2::new(4, 7)));
+        grid.remove(Point2::new(4, 7));
+        assert_eq!(grid.get_bounds(), (Point2::new(-3, 0), Point2::new(0, 1)));
+
+        grid.retain(|location, _| location.x == 0);
+        assert_eq!(grid.get_bounds(), (Point2::ORIGIN, Point2::ORIGIN));
+        grid.retain(|_, _| false);
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn retains_and_updates_cells() {
+        let mut grid = grid(&[(0, 0, 1), (1, 0, 2), (2, 0, 3), (9, 9, 4)]);
+        grid.retain(|location, value| {
+            *value *= 2;
+            location.y == 0
+        });
+        assert_eq!(grid.get(Point2::new(2, 0)), Some(&6));
+        assert_eq!(grid.get_bounds(), (Point2::ORIGIN, Point2::new(2, 0)));
+    }
+
+    #[test]
+    fn answers_range_and_count_queries() {
+        let mut grid = grid(&[(-2, 1, 1), (3, 1, 2), (0, 4, 3), (0, -1, 4)]);
+        assert_eq!(grid.row_range(1), Some((-2, 3)));
+        assert_eq!(grid.row_range(2), None);
+        assert_eq!(grid.column_range(0), Some((-1, 4)));
+        assert_eq!(grid.count_where(|_, v| v % 2 == 0), 2);
+        assert_eq!(grid.count_where(|l, _| l.y == 1), 2);
+
+        let bounds = grid.get_bounds();
+        grid.recompute_bounds();
+        assert_eq!(grid.get_bounds(), bounds);
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    geom::Point2,
+    grid::{Grid, SparseGrid},
+};
+
+pub type PointN<const N: usize> = [isize; N];
+
+#[derive(Debug, Clone)]
+pub struct SparseGridN<T, const N: usize>
+where
+    T: core::fmt::Debug + Default,
+{
+    bounds: (PointN<N>, PointN<N>),
+    data: HashMap<PointN<N>, T>,
+}
+
+impl<T, const N: usize> Default for SparseGridN<T, N>
+where
+    T: core::fmt::Debug + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> SparseGridN<T, N>
+where
+    T: core::fmt::Debug + Default,
+{
+    pub fn new() -> Self {
+        Self {
+            bounds: ([0; N], [0; N]),
+            data: HashMap::new(),
+        }
+    }
+
+    pub fn get_bounds(&self) -> (PointN<N>, PointN<N>) {
+        self.bounds
+    }
+
+    pub fn get(&self, location: PointN<N>) -> Option<&T> {
+        self.data.get(&location)
+    }
+
+    pub fn set(&mut self, location: PointN<N>, value: T) {
+        if self.data.is_empty() {
+            self.bounds = (location, location);
+        } else {
+            for (axis, coord) in location.iter().enumerate() {
+                self.bounds.0[axis] = self.bounds.0[axis].min(*coord);
+                self.bounds.1[axis] = self.bounds.1[axis].max(*coord);
+            }
+        }
+        self.data.insert(location, value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PointN<N>, &T)> {
+        self.data.iter()
+    }
+
+    // The plane through `at` spanned by two different axes, with the first
+    // axis drawn as x.
+    pub fn slice(
+        &self,
+        axes: (usize, usize),
+        at: PointN<N>,
+        renderer: Box<dyn Fn(&T) -> char>,
+    ) -> Result<SparseGrid<T>>
+    where
+        T: Clone,
+    {
+        if axes.0 == axes.1 {
+            bail!("Can't slice along axis {} twice", axes.0);
+        }
+        if axes.0 >= N || axes.1 >= N {
+            bail!("Axes {:?} don't exist in {} dimensions", axes, N);
+        }
+        let mut grid = SparseGrid::new(renderer);
+
+        for (location, value) in self.data.iter() {
+            let on_slice = (0..N)
+                .filter(|axis| *axis != axes.0 && *axis != axes.1)
+                .all(|axis| location[axis] == at[axis]);
+            if on_slice {
+                grid.set(
+                    Point2::new(location[axes.0], location[axes.1]),
+                    value.clone(),
+                );
+            }
+        }
+
+        Ok(grid)
+    }
+
+    pub fn flood_fill(
+        &self,
+        start: PointN<N>,
+        window: (PointN<N>, PointN<N>),
+        passable: impl Fn(Option<&T>) -> bool,
+    ) -> HashSet<PointN<N>> {
+        let mut filled = HashSet::new();
+        if !in_window(start, window) || !passable(self.get(start)) {
+            return filled;
+        }
+
+        let mut queue = VecDeque::from([start]);
+        filled.insert(start);
+
+        while let Some(location) = queue.pop_front() {
+            for neighbor in face_neighbors(location) {
+                if in_window(neighbor, window)
+                    && !filled.contains(&neighbor)
+                    && passable(self.get(neighbor))
+                {
+                    filled.insert(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        filled
+    }
+}
+
+pub fn face_neighbors<const N: usize>(location: PointN<N>) -> Vec<PointN<N>> {
+    let mut neighbors = Vec::with_capacity(N * 2);
+    for axis in 0..N {
+        for delta in [-1, 1] {
+            let mut neighbor = location;
+            neighbor[axis] += delta;
+            neighbors.push(neighbor);
+        }
+    }
+
+    neighbors
+}
+
+pub fn all_neighbors<const N: usize>(location: PointN<N>) -> Vec<PointN<N>> {
+    let count = 3usize.pow(N as u32);
+    let mut neighbors = Vec::with_capacity(count - 1);
+    for offset in 0..count {
+        let mut neighbor = location;
+        let mut rest = offset;
+        for coord in neighbor.iter_mut() {
+            *coord += (rest % 3) as isize - 1;
+            rest /= 3;
+        }
+        if neighbor != location {
+            neighbors.push(neighbor);
+        }
+    }
+
+    neighbors
+}
+
+fn in_window<const N: usize>(location: PointN<N>, window: (PointN<N>, PointN<N>)) -> bool {
+    (0..N).all(|axis| location[axis] >= window.0[axis] && location[axis] <= window.1[axis])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_neighbors, face_neighbors, SparseGridN};
+    use crate::grid::Grid;
+
+    #[test]
+    fn finds_neighbors_in_any_dimension() {
+        let mut faces = face_neighbors([0, 0, 0]);
+        faces.sort();
+        assert_eq!(
+            faces,
+            vec![
+                [-1, 0, 0],
+                [0, -1, 0],
+                [0, 0, -1],
+                [0, 0, 1],
+                [0, 1, 0],
+                [1, 0, 0]
+            ]
+        );
+
+        assert_eq!(all_neighbors([5]), vec![[4], [6]]);
+        assert_eq!(all_neighbors([0, 0]).len(), 8);
+        let around = all_neighbors([1, 2, 3, 4]);
+        assert_eq!(around.len(), 80);
+        assert!(!around.contains(&[1, 2, 3, 4]));
+        assert!(around.contains(&[0, 3, 2, 5]));
+    }
+
+    #[test]
+    fn slices_a_plane() {
+        let mut grid = SparseGridN::<u8, 3>::new();
+        grid.set([1, 2, 0], 1);
+        grid.set([3, 4, 0], 2);
+        grid.set([1, 2, 1], 3);
+
+        let slice = grid
+            .slice((1, 0), [0, 0, 0], Box::new(|v| (b'0' + v) as char))
+            .unwrap();
+        assert_eq!(slice.len(), 2);
+        assert_eq!(slice.get((2, 1).into()), Some(&1));
+        assert_eq!(slice.get((4, 3).into()), Some(&2));
+
+        let render = |v: &u8| (b'0' + v) as char;
+        assert!(grid.slice((0, 0), [0; 3], Box::new(render)).is_err());
+        assert!(grid.slice((0, 3), [0; 3], Box::new(render)).is_err());
+    }
+
+    #[test]
+    fn flood_fills_inside_the_window() {
+        // A wall at x = 2 splits the window in two.
+        let mut grid = SparseGridN::<bool, 2>::new();
+        for y in 0..=3 {
+            grid.set([2, y], true);
+        }
+        let open = |cell: Option<&bool>| !cell.copied().unwrap_or(false);
+
+        let filled = grid.flood_fill([0, 0], ([0, 0], [4, 3]), open);
+        assert_eq!(filled.len(), 8);
+        assert!(filled.iter().all(|p| p[0] < 2));
+
+        let filled = grid.flood_fill([0, 0], ([-1, -1], [4, 4]), open);
+        assert_eq!(filled.len(), 6 * 6 - 4);
+        assert!(grid.flood_fill([2, 0], ([0, 0], [4, 3]), open).is_empty());
+        assert!(grid.flood_fill([9, 9], ([0, 0], [4, 3]), open).is_empty());
+    }
+}
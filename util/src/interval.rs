@@ -0,0 +1,318 @@
+use std::ops::{Add, Sub};
+
+pub trait Integer: Copy + Ord + core::fmt::Debug + Add<Output = Self> + Sub<Output = Self> {
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    // How far apart two values are. Wide enough for any pair of values, even
+    // `MIN` and `MAX` of a signed type.
+    fn distance(self, other: Self) -> u128;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(impl Integer for $t {
+            const ONE: Self = 1;
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_sub(self, rhs)
+            }
+
+            fn distance(self, other: Self) -> u128 {
+                <$t>::abs_diff(self, other) as u128
+            }
+        })*
+    };
+}
+
+impl_integer!(i32, i64, isize, u32, u64, usize);
+
+// Whether `a` ends with a gap before `b`, so the two can't be merged. Nothing
+// comes after `T::MAX`, so it never leaves a gap.
+fn gap_between<T: Integer>(a: T, b: T) -> bool {
+    a.checked_add(T::ONE).is_some_and(|next| next < b)
+}
+
+// Intervals are inclusive on both ends and kept sorted, disjoint and
+// non-adjacent, so [1, 3] and [4, 6] are stored as [1, 6].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet<T: Integer> {
+    intervals: Vec<(T, T)>,
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { intervals: vec![] }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(T, T)> {
+        self.intervals.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn bounds(&self) -> Option<(T, T)> {
+        Some((self.intervals.first()?.0, self.intervals.last()?.1))
+    }
+
+    pub fn insert(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        let mut start = start;
+        let mut end = end;
+        let mut merged = Vec::with_capacity(self.intervals.len() + 1);
+        let mut placed = false;
+
+        for &(s, e) in self.intervals.iter() {
+            if gap_between(e, start) {
+                merged.push((s, e));
+            } else if gap_between(end, s) {
+                if !placed {
+                    merged.push((start, end));
+                    placed = true;
+                }
+                merged.push((s, e));
+            } else {
+                start = start.min(s);
+                end = end.max(e);
+            }
+        }
+        if !placed {
+            merged.push((start, end));
+        }
+
+        self.intervals = merged;
+    }
+
+    pub fn merge(&mut self, other: &IntervalSet<T>) {
+        for &(start, end) in other.iter() {
+            self.insert(start, end);
+        }
+    }
+
+    pub fn subtract(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        let mut remaining = Vec::with_capacity(self.intervals.len() + 1);
+
+        for &(s, e) in self.intervals.iter() {
+            if e < start || s > end {
+                remaining.push((s, e));
+                continue;
+            }
+            if let Some(before) = start.checked_sub(T::ONE).filter(|&b| s <= b) {
+                remaining.push((s, before));
+            }
+            if let Some(after) = end.checked_add(T::ONE).filter(|&a| a <= e) {
+                remaining.push((after, e));
+            }
+        }
+
+        self.intervals = remaining;
+    }
+
+    pub fn subtract_set(&mut self, other: &IntervalSet<T>) {
+        for &(start, end) in other.iter() {
+            self.subtract(start, end);
+        }
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a_start, a_end) = self.intervals[i];
+            let (b_start, b_end) = other.intervals[j];
+            let start = a_start.max(b_start);
+            let end = a_end.min(b_end);
+            if start <= end {
+                result.intervals.push((start, end));
+            }
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        result
+    }
+
+    // The number of values covered. A set covering every `u64` holds 2^64 of
+    // them, so the count doesn't fit `T`.
+    pub fn total_length(&self) -> u128 {
+        self.intervals.iter().map(|&(s, e)| e.distance(s) + 1).sum()
+    }
+
+    pub fn gaps(&self) -> Vec<(T, T)> {
+        self.intervals
+            .windows(2)
+            .filter_map(|w| Some((w[0].1.checked_add(T::ONE)?, w[1].0.checked_sub(T::ONE)?)))
+            .collect()
+    }
+
+    pub fn gaps_within(&self, start: T, end: T) -> Vec<(T, T)> {
+        let mut window = IntervalSet::new();
+        window.insert(start, end);
+        window.subtract_set(self);
+        window.intervals
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.contains_interval(value, value)
+    }
+
+    pub fn contains_interval(&self, start: T, end: T) -> bool {
+        self.intervals.iter().any(|&(s, e)| s <= start && end <= e)
+    }
+
+    pub fn overlaps(&self, start: T, end: T) -> bool {
+        self.intervals.iter().any(|&(s, e)| s <= end && start <= e)
+    }
+}
+
+impl<T: Integer> From<(T, T)> for IntervalSet<T> {
+    fn from((start, end): (T, T)) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(start, end);
+        set
+    }
+}
+
+impl<T: Integer> FromIterator<(T, T)> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for (start, end) in iter {
+            set.insert(start, end);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalSet;
+
+    #[test]
+    fn handles_intervals_at_the_type_limits() {
+        let mut set = IntervalSet::<i32>::new();
+        set.insert(i32::MAX - 1, i32::MAX);
+        set.insert(i32::MIN, i32::MIN + 1);
+        set.insert(0, 0);
+        assert_eq!(set.gaps(), vec![(i32::MIN + 2, -1), (1, i32::MAX - 2)]);
+
+        set.insert(i32::MAX - 3, i32::MAX - 3);
+        set.insert(i32::MAX - 2, i32::MAX);
+        set.subtract(i32::MIN, i32::MIN);
+        set.subtract(i32::MAX, i32::MAX);
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            vec![
+                (i32::MIN + 1, i32::MIN + 1),
+                (0, 0),
+                (i32::MAX - 3, i32::MAX - 1)
+            ]
+        );
+
+        let mut full = IntervalSet::<u32>::from((0, u32::MAX));
+        full.subtract(0, 0);
+        full.subtract(u32::MAX, u32::MAX);
+        assert_eq!(full.bounds(), Some((1, u32::MAX - 1)));
+        assert_eq!(full.total_length(), u32::MAX as u128 - 1);
+    }
+
+    #[test]
+    fn counts_every_value_of_the_type() {
+        assert_eq!(
+            IntervalSet::<i32>::from((i32::MIN, i32::MAX)).total_length(),
+            1 << 32
+        );
+        assert_eq!(
+            IntervalSet::<u32>::from((0, u32::MAX)).total_length(),
+            1 << 32
+        );
+        assert_eq!(
+            IntervalSet::<i64>::from((i64::MIN, i64::MAX)).total_length(),
+            1 << 64
+        );
+        assert_eq!(
+            IntervalSet::<u64>::from((0, u64::MAX)).total_length(),
+            1 << 64
+        );
+        assert_eq!(IntervalSet::<i32>::new().total_length(), 0);
+        assert_eq!(IntervalSet::from_iter([(-5, -1), (3, 3)]).total_length(), 6);
+    }
+
+    #[test]
+    fn merges_overlapping_and_adjacent_intervals() {
+        let mut set = IntervalSet::new();
+        set.insert(10, 12);
+        set.insert(1, 3);
+        set.insert(20, 25);
+        set.insert(4, 5);
+        set.insert(7, 9);
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            vec![(1, 5), (7, 12), (20, 25)]
+        );
+
+        set.insert(5, 21);
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![(1, 25)]);
+
+        // Empty intervals are ignored.
+        set.insert(40, 30);
+        assert_eq!(set.bounds(), Some((1, 25)));
+
+        let mut other = IntervalSet::from((27, 30));
+        other.merge(&set);
+        assert_eq!(
+            other.iter().copied().collect::<Vec<_>>(),
+            vec![(1, 25), (27, 30)]
+        );
+    }
+
+    #[test]
+    fn intersects_sets() {
+        let a = IntervalSet::from_iter([(0, 5), (10, 15), (20, 30)]);
+        let b = IntervalSet::from_iter([(3, 12), (14, 22), (30, 40)]);
+        assert_eq!(
+            a.intersection(&b).iter().copied().collect::<Vec<_>>(),
+            vec![(3, 5), (10, 12), (14, 15), (20, 22), (30, 30)]
+        );
+        assert_eq!(a.intersection(&b), b.intersection(&a));
+        assert!(a.intersection(&IntervalSet::from((6, 9))).is_empty());
+    }
+
+    #[test]
+    fn finds_gaps_inside_a_window() {
+        let set = IntervalSet::from_iter([(2, 4), (8, 10)]);
+        assert_eq!(set.gaps(), vec![(5, 7)]);
+        assert_eq!(set.gaps_within(0, 12), vec![(0, 1), (5, 7), (11, 12)]);
+        assert_eq!(set.gaps_within(3, 9), vec![(5, 7)]);
+        assert_eq!(set.gaps_within(8, 9), vec![]);
+    }
+
+    #[test]
+    fn checks_containment_and_overlap() {
+        let set = IntervalSet::from_iter([(2, 4), (8, 10)]);
+        assert!(set.contains(2) && set.contains(10));
+        assert!(!set.contains(5) && !set.contains(11));
+        assert!(set.contains_interval(8, 10));
+        assert!(!set.contains_interval(4, 8));
+        assert!(set.overlaps(4, 8));
+        assert!(set.overlaps(0, 2));
+        assert!(!set.overlaps(5, 7));
+    }
+}
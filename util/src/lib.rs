@@ -1,8 +1,19 @@
 use anyhow::Result;
 use std::{env, fs};
 
+pub mod crane;
+pub mod disk_usage;
+pub mod filesystem;
+pub mod geom;
 pub mod grid;
+pub mod grid_n;
+pub mod interval;
 pub mod linked_grid;
+pub mod monkey;
+pub mod ocr;
+pub mod rope;
+pub mod transcript;
+pub mod vm;
 
 pub struct Input {
     filename: String,
@@ -3,6 +3,8 @@ use std::ops::Index;
 use anyhow::{bail, Result};
 use petgraph::{stable_graph::NodeIndex, Graph};
 
+use crate::geom::Point2;
+
 #[derive(Debug, Clone)]
 pub struct Point<T: core::fmt::Debug + Clone> {
     pub x: usize,
@@ -10,6 +12,18 @@ pub struct Point<T: core::fmt::Debug + Clone> {
     pub data: T,
 }
 
+impl<T: core::fmt::Debug + Clone> Point<T> {
+    pub fn position(&self) -> Point2 {
+        Point2::new(self.x as isize, self.y as isize)
+    }
+}
+
+impl<T: core::fmt::Debug + Clone> From<&Point<T>> for Point2 {
+    fn from(point: &Point<T>) -> Self {
+        point.position()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LinkedGrid<T: core::fmt::Debug + Clone> {
     graph: Graph<Point<T>, ()>,
@@ -21,9 +35,8 @@ impl<T: core::fmt::Debug + Clone> LinkedGrid<T> {
         let mut graph = Graph::<Point<T>, ()>::new();
         let mut indicies = vec![vec![NodeIndex::default(); width]; height];
         for x in 0..width {
-            #[allow(clippy::needless_range_loop)]
-            for y in 0..height {
-                indicies[y][x] = graph.add_node(Point {
+            for (y, row) in indicies.iter_mut().enumerate() {
+                row[x] = graph.add_node(Point {
                     x,
                     y,
                     data: fdata(x, y),
@@ -34,16 +47,12 @@ impl<T: core::fmt::Debug + Clone> LinkedGrid<T> {
         Self { graph, indicies }
     }
 
-    pub fn try_link(&mut self, from: (isize, isize), to: (isize, isize)) -> Result<()> {
-        if !self.in_bounds(from) || !self.in_bounds(to) {
+    pub fn try_link(&mut self, from: impl Into<Point2>, to: impl Into<Point2>) -> Result<()> {
+        let (Some(from), Some(to)) = (self.node(from.into()), self.node(to.into())) else {
             bail!("Point out of bounds");
-        }
+        };
 
-        self.graph.add_edge(
-            self.indicies[from.1 as usize][from.0 as usize],
-            self.indicies[to.1 as usize][to.0 as usize],
-            (),
-        );
+        self.graph.add_edge(from, to, ());
 
         Ok(())
     }
@@ -56,16 +65,21 @@ impl<T: core::fmt::Debug + Clone> LinkedGrid<T> {
         }
     }
 
-    fn in_bounds(&self, location: (isize, isize)) -> bool {
-        location.0 >= 0
-            && location.1 >= 0
-            && (location.0 as usize) < self.indicies[0].len()
-            && (location.1 as usize) < self.indicies.len()
+    fn node(&self, location: Point2) -> Option<NodeIndex> {
+        let x = usize::try_from(location.x).ok()?;
+        let y = usize::try_from(location.y).ok()?;
+
+        self.indicies.get(y)?.get(x).copied()
     }
 
-    pub fn neighbors(&self, location: (usize, usize)) -> Vec<&Point<T>> {
+    // Points outside the grid have no neighbors.
+    pub fn neighbors(&self, location: impl Into<Point2>) -> Vec<&Point<T>> {
+        let Some(index) = self.node(location.into()) else {
+            return vec![];
+        };
+
         self.graph
-            .neighbors(self.indicies[location.1][location.0])
+            .neighbors(index)
             .map(|n| &self.graph[n])
             .collect()
     }
@@ -85,16 +99,20 @@ impl<T: core::fmt::Debug + Clone> LinkedGrid<T> {
         }
     }
 
-    pub fn get_data(&self, x: usize, y: usize) -> Result<&T> {
-        Ok(&self.graph[self.indicies[y][x]].data)
+    pub fn get_data(&self, location: impl Into<Point2>) -> Result<&T> {
+        let Some(index) = self.node(location.into()) else {
+            bail!("Out of bounds");
+        };
+
+        Ok(&self.graph[index].data)
     }
 
-    pub fn set_data(&mut self, x: usize, y: usize, new_data: T) -> Result<()> {
-        if !self.in_bounds((x as isize, y as isize)) {
+    pub fn set_data(&mut self, location: impl Into<Point2>, new_data: T) -> Result<()> {
+        let Some(index) = self.node(location.into()) else {
             bail!("Out of bounds");
-        }
+        };
 
-        self.graph[self.indicies[y][x]].data = new_data;
+        self.graph[index].data = new_data;
 
         Ok(())
     }
@@ -107,3 +125,13 @@ impl<T: core::fmt::Debug + Clone> Index<(usize, usize)> for LinkedGrid<T> {
         &self.graph[self.indicies[index.1][index.0]]
     }
 }
+
+impl<T: core::fmt::Debug + Clone> Index<Point2> for LinkedGrid<T> {
+    type Output = Point<T>;
+
+    fn index(&self, index: Point2) -> &Self::Output {
+        let index = self.node(index).expect("point is in the grid");
+
+        &self.graph[index]
+    }
+}
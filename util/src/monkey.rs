@@ -0,0 +1,1197 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    fn symbol(self) -> char {
+        match self {
+            BinOp::Add => '+',
+            BinOp::Sub => '-',
+            BinOp::Mul => '*',
+            BinOp::Div => '/',
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Sub => 1,
+            BinOp::Mul | BinOp::Div => 2,
+        }
+    }
+
+    fn apply(self, a: i64, b: i64) -> Result<i64> {
+        let result = match self {
+            BinOp::Add => a.checked_add(b),
+            BinOp::Sub => a.checked_sub(b),
+            BinOp::Mul => a.checked_mul(b),
+            BinOp::Div if b == 0 => bail!("{} / 0 divides by zero", a),
+            BinOp::Div => a.checked_div(b),
+        };
+
+        result.ok_or_else(|| anyhow!("{} {} {} overflows", a, self.symbol(), b))
+    }
+}
+
+// A monkey's operation, the right hand side of `new = ...`. Division is
+// integer division, so it doesn't mix with `Relief::Modulo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Int(i64),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, old: i64) -> Result<i64> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Int(v) => Ok(*v),
+            Expr::Binary(op, a, b) => op.apply(a.eval(old)?, b.eval(old)?),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Int(v) => write!(f, "{v}"),
+            Expr::Binary(op, a, b) => {
+                for (idx, side) in [a, b].into_iter().enumerate() {
+                    if idx == 1 {
+                        write!(f, " {} ", op.symbol())?;
+                    }
+                    // Right operands need parentheses at equal precedence
+                    // too, since `a - (b - c)` isn't `a - b - c`.
+                    let wrap = match side.as_ref() {
+                        Expr::Binary(inner, _, _) => {
+                            inner.precedence() < op.precedence()
+                                || (idx == 1 && inner.precedence() == op.precedence())
+                        }
+                        _ => false,
+                    };
+                    if wrap {
+                        write!(f, "({side})")?;
+                    } else {
+                        write!(f, "{side}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Int(i64),
+    Old,
+    Op(BinOp),
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(BinOp::Add),
+            '-' => Token::Op(BinOp::Sub),
+            '*' => Token::Op(BinOp::Mul),
+            '/' => Token::Op(BinOp::Div),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(d);
+                }
+                Token::Int(digits.parse()?)
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some(d) = chars.next_if(char::is_ascii_alphanumeric) {
+                    word.push(d);
+                }
+                if word != "old" {
+                    bail!("Unknown name '{}' in '{}'", word, s);
+                }
+                Token::Old
+            }
+            c => bail!("Unexpected '{}' in '{}'", c, s),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct ExprParser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Int(v)) => Ok(Expr::Int(v)),
+            Some(Token::Old) => Ok(Expr::Old),
+            Some(Token::Op(BinOp::Sub)) => Ok(Expr::Binary(
+                BinOp::Sub,
+                Box::new(Expr::Int(0)),
+                Box::new(self.atom()?),
+            )),
+            Some(Token::Open) => {
+                let expr = self.expr(0)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => bail!("Missing ')' in '{}'", self.source),
+                }
+            }
+            _ => bail!("Expected a value in '{}'", self.source),
+        }
+    }
+
+    fn expr(&mut self, min_precedence: u8) -> Result<Expr> {
+        let mut lhs = self.atom()?;
+        while let Some(Token::Op(op)) = self.tokens.get(self.pos).copied() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.expr(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = ExprParser {
+            source: s,
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.expr(0)?;
+        if parser.pos != parser.tokens.len() {
+            bail!("Unexpected trailing input in '{}'", s);
+        }
+
+        Ok(expr)
+    }
+}
+
+// How worry levels drop after each inspection.
+pub enum Relief {
+    DivideBy(i64),
+    Modulo(i64),
+    Custom(Box<dyn Fn(i64) -> i64>),
+}
+
+impl Relief {
+    pub fn apply(&self, worry: i64) -> i64 {
+        match self {
+            Relief::DivideBy(d) => worry / d,
+            Relief::Modulo(m) => worry.rem_euclid(*m),
+            Relief::Custom(f) => f(worry),
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// The smallest modulus that keeps every monkey's divisibility test correct.
+pub fn group_modulus(monkeys: &[Monkey]) -> i64 {
+    monkeys
+        .iter()
+        .fold(1, |acc, m| acc / gcd(acc, m.test_divisor) * m.test_divisor)
+}
+
+// Item ids are handed out by `Simulation::new`, numbering items in monkey
+// order, so every simulation of the same input sees the same ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Item {
+    pub id: usize,
+    pub worry_level: i64,
+}
+
+impl Item {
+    pub fn new(worry_level: i64) -> Self {
+        Self { id: 0, worry_level }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Monkey {
+    pub id: usize,
+    pub items: Vec<Item>,
+    pub operation: Expr,
+    pub test_divisor: i64,
+    pub if_true: usize,
+    pub if_false: usize,
+    pub inspect_count: usize,
+}
+
+impl Monkey {
+    pub fn target(&self, worry_level: i64) -> usize {
+        if worry_level % self.test_divisor == 0 {
+            self.if_true
+        } else {
+            self.if_false
+        }
+    }
+}
+
+// Per-monkey vectors are in the same order as `Simulation::monkeys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundStats {
+    pub round: usize,
+    pub inspections: Vec<usize>,
+    pub totals: Vec<usize>,
+    pub max_worry: Option<i64>,
+}
+
+// An item's trajectory repeats every `length` rounds from round `start` on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemCycle {
+    pub item: usize,
+    pub start: usize,
+    pub length: usize,
+}
+
+// The monkeys that inspected one item, in order. Round `r` from now covers
+// `visits[ends[r - 1]..ends[r]]`, with `ends[0]` being 0.
+struct Trajectory {
+    visits: Vec<usize>,
+    ends: Vec<usize>,
+    cycle: Option<ItemCycle>,
+}
+
+impl Trajectory {
+    // Per-monkey inspections during rounds `from + 1..=to`, counting from now.
+    fn counts_between(&self, from: usize, to: usize, monkeys: usize) -> Vec<usize> {
+        let mut counts = vec![0; monkeys];
+        for idx in &self.visits[self.ends[from]..self.ends[to]] {
+            counts[*idx] += 1;
+        }
+
+        counts
+    }
+}
+
+pub struct Simulation {
+    monkeys: Vec<Monkey>,
+    index: HashMap<usize, usize>,
+    relief: Relief,
+    round: usize,
+    item_count: usize,
+    lineage: Option<Vec<Vec<usize>>>,
+}
+
+impl Simulation {
+    pub fn new(mut monkeys: Vec<Monkey>, relief: Relief) -> Result<Self> {
+        match relief {
+            Relief::DivideBy(0) => bail!("Relief can't divide by zero"),
+            Relief::Modulo(0) => bail!("Relief can't take worry levels modulo zero"),
+            _ => {}
+        }
+        monkeys.sort_by_key(|m| m.id);
+
+        let mut index = HashMap::new();
+        for (idx, monkey) in monkeys.iter().enumerate() {
+            if index.insert(monkey.id, idx).is_some() {
+                bail!("Monkey {} is defined twice", monkey.id);
+            }
+            if monkey.test_divisor == 0 {
+                bail!("Monkey {} tests divisibility by zero", monkey.id);
+            }
+        }
+        for monkey in monkeys.iter() {
+            for target in [monkey.if_true, monkey.if_false] {
+                if !index.contains_key(&target) {
+                    bail!("Monkey {} throws to missing monkey {}", monkey.id, target);
+                }
+            }
+        }
+
+        let mut item_count = 0;
+        for item in monkeys.iter_mut().flat_map(|m| m.items.iter_mut()) {
+            item.id = item_count;
+            item_count += 1;
+        }
+
+        Ok(Self {
+            monkeys,
+            index,
+            relief,
+            round: 0,
+            item_count,
+            lineage: None,
+        })
+    }
+
+    // Starts recording the monkeys each item is thrown to. Each item's
+    // lineage begins with the monkey holding it when tracking starts.
+    pub fn track_lineage(&mut self) {
+        let mut lineage = vec![vec![]; self.item_count];
+        for monkey in self.monkeys.iter() {
+            for item in monkey.items.iter() {
+                lineage[item.id].push(monkey.id);
+            }
+        }
+        self.lineage = Some(lineage);
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_count
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (usize, &Item)> {
+        self.monkeys
+            .iter()
+            .flat_map(|m| m.items.iter().map(move |item| (m.id, item)))
+    }
+
+    pub fn lineage(&self, item: usize) -> Option<&[usize]> {
+        self.lineage.as_ref()?.get(item).map(Vec::as_slice)
+    }
+
+    // Every time an item came back to a monkey it had already visited, the
+    // monkeys it passed through since its previous visit there, starting with
+    // that monkey.
+    pub fn loops(&self, item: usize) -> Vec<&[usize]> {
+        let Some(path) = self.lineage(item) else {
+            return vec![];
+        };
+
+        let mut last_visit = HashMap::new();
+        let mut loops = vec![];
+        for (idx, monkey) in path.iter().enumerate() {
+            if let Some(previous) = last_visit.insert(*monkey, idx) {
+                loops.push(&path[previous..idx]);
+            }
+        }
+
+        loops
+    }
+
+    // How often each loop shows up across all items, most common first.
+    pub fn loop_counts(&self) -> Vec<(Vec<usize>, usize)> {
+        let mut counts: HashMap<&[usize], usize> = HashMap::new();
+        for item in 0..self.item_count {
+            for l in self.loops(item) {
+                *counts.entry(l).or_default() += 1;
+            }
+        }
+
+        let mut counts = counts
+            .into_iter()
+            .map(|(l, count)| (l.to_vec(), count))
+            .collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        counts
+    }
+
+    pub fn monkeys(&self) -> &[Monkey] {
+        &self.monkeys
+    }
+
+    pub fn monkey(&self, id: usize) -> Option<&Monkey> {
+        self.index.get(&id).map(|idx| &self.monkeys[*idx])
+    }
+
+    pub fn rounds_played(&self) -> usize {
+        self.round
+    }
+
+    pub fn round(&mut self) -> Result<RoundStats> {
+        let before = self
+            .monkeys
+            .iter()
+            .map(|m| m.inspect_count)
+            .collect::<Vec<_>>();
+
+        for idx in 0..self.monkeys.len() {
+            for mut item in std::mem::take(&mut self.monkeys[idx].items) {
+                self.monkeys[idx].inspect_count += 1;
+
+                let (target, worry_level) = self.inspect(idx, item.worry_level)?;
+                item.worry_level = worry_level;
+                if let Some(lineage) = self.lineage.as_mut() {
+                    lineage[item.id].push(self.monkeys[target].id);
+                }
+                self.monkeys[target].items.push(item);
+            }
+        }
+        self.round += 1;
+
+        let totals = self
+            .monkeys
+            .iter()
+            .map(|m| m.inspect_count)
+            .collect::<Vec<_>>();
+        Ok(RoundStats {
+            round: self.round,
+            inspections: totals.iter().zip(before).map(|(t, b)| t - b).collect(),
+            totals,
+            max_worry: self
+                .monkeys
+                .iter()
+                .flat_map(|m| m.items.iter().map(|i| i.worry_level))
+                .max(),
+        })
+    }
+
+    // Returns the index of the monkey the item is thrown to and its new worry
+    // level.
+    fn inspect(&self, idx: usize, worry_level: i64) -> Result<(usize, i64)> {
+        let monkey = &self.monkeys[idx];
+        let worry = monkey
+            .operation
+            .eval(worry_level)
+            .map_err(|e| anyhow!("Monkey {}: {}", monkey.id, e))?;
+        let worry = self.relief.apply(worry);
+
+        Ok((self.index[&monkey.target(worry)], worry))
+    }
+
+    pub fn run(&mut self, rounds: usize) -> Result<Vec<RoundStats>> {
+        (0..rounds).map(|_| self.round()).collect()
+    }
+
+    // Follows one item on its own for up to `max_rounds` rounds. Items never
+    // affect each other, and within a round an item keeps moving until it's
+    // thrown to a monkey that has already had its turn, so its state between
+    // rounds is just its holder and worry level.
+    fn trajectory(&self, item: usize, max_rounds: usize) -> Result<Trajectory> {
+        let max_rounds = self.cycle_bound().map_or(max_rounds, |b| b.min(max_rounds));
+        let (mut idx, mut worry) = self
+            .monkeys
+            .iter()
+            .enumerate()
+            .find_map(|(idx, m)| {
+                m.items
+                    .iter()
+                    .find(|i| i.id == item)
+                    .map(|i| (idx, i.worry_level))
+            })
+            .ok_or_else(|| anyhow!("No item {}", item))?;
+
+        let mut seen = HashMap::from([((idx, worry), 0)]);
+        let mut visits = vec![];
+        let mut ends = vec![0];
+        for round in 1..=max_rounds {
+            loop {
+                visits.push(idx);
+                let (target, new_worry) = self.inspect(idx, worry)?;
+                let moves_on = target > idx;
+                idx = target;
+                worry = new_worry;
+                if !moves_on {
+                    break;
+                }
+            }
+            ends.push(visits.len());
+
+            if let Some(start) = seen.insert((idx, worry), round) {
+                return Ok(Trajectory {
+                    visits,
+                    ends,
+                    cycle: Some(ItemCycle {
+                        item,
+                        start: self.round + start,
+                        length: round - start,
+                    }),
+                });
+            }
+        }
+
+        Ok(Trajectory {
+            visits,
+            ends,
+            cycle: None,
+        })
+    }
+
+    // With modulo relief an item has at most `modulus * monkeys` states, so
+    // it must repeat within that many rounds. Other reliefs give no bound.
+    fn cycle_bound(&self) -> Option<usize> {
+        match self.relief {
+            Relief::Modulo(m) => usize::try_from(m)
+                .ok()
+                .and_then(|m| m.checked_mul(self.monkeys.len())),
+            _ => None,
+        }
+    }
+
+    // Finds the round after which `item` repeats the same rounds forever,
+    // searching at most `max_rounds` rounds ahead of the current one.
+    pub fn item_cycle(&self, item: usize, max_rounds: usize) -> Result<Option<ItemCycle>> {
+        Ok(self.trajectory(item, max_rounds)?.cycle)
+    }
+
+    // Inspection counts per monkey once `round` rounds have been played, in
+    // the same order as `monkeys`. Each item is only simulated until its
+    // trajectory repeats, so this is cheap even for huge round numbers.
+    pub fn inspection_counts_at(&self, round: usize) -> Result<Vec<usize>> {
+        if round < self.round {
+            bail!("Round {} has already been played", round);
+        }
+        let remaining = round - self.round;
+
+        let mut totals = self
+            .monkeys
+            .iter()
+            .map(|m| m.inspect_count)
+            .collect::<Vec<_>>();
+        let monkeys = self.monkeys.len();
+        for (_, item) in self.items() {
+            let trajectory = self.trajectory(item.id, remaining)?;
+            let Some(cycle) = trajectory.cycle else {
+                let simulated = trajectory.ends.len() - 1;
+                if simulated < remaining {
+                    bail!("Item {} didn't repeat within {} rounds", item.id, simulated);
+                }
+                for (total, count) in totals
+                    .iter_mut()
+                    .zip(trajectory.counts_between(0, remaining, monkeys))
+                {
+                    *total += count;
+                }
+                continue;
+            };
+
+            let start = cycle.start - self.round;
+            let repeats = (remaining - start) / cycle.length;
+            let offset = (remaining - start) % cycle.length;
+            let lead_in = trajectory.counts_between(0, start, monkeys);
+            let per_cycle = trajectory.counts_between(start, start + cycle.length, monkeys);
+            let tail = trajectory.counts_between(start, start + offset, monkeys);
+            for (i, total) in totals.iter_mut().enumerate() {
+                *total += lead_in[i] + repeats * per_cycle[i] + tail[i];
+            }
+        }
+
+        Ok(totals)
+    }
+
+    // Like `monkey_business`, but for the state after `round` rounds. Wide
+    // enough not to overflow for rounds in the billions.
+    pub fn monkey_business_at(&self, round: usize, top: usize) -> Result<u128> {
+        let mut counts = self.inspection_counts_at(round)?;
+        counts.sort_by(|a, b| b.cmp(a));
+
+        Ok(counts.into_iter().take(top).map(|c| c as u128).product())
+    }
+
+    // The product of the `top` highest inspection counts.
+    pub fn monkey_business(&self, top: usize) -> usize {
+        let mut counts = self
+            .monkeys
+            .iter()
+            .map(|m| m.inspect_count)
+            .collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.cmp(a));
+
+        counts.into_iter().take(top).product()
+    }
+}
+
+mod parser {
+    use std::str::FromStr;
+
+    use anyhow::{bail, Result};
+    use nom::{
+        bytes::complete::tag,
+        character::complete::{char, digit1, space0, space1},
+        combinator::{all_consuming, map_res, rest},
+        multi::separated_list0,
+        sequence::{delimited, tuple},
+        IResult,
+    };
+
+    fn number<T: FromStr>(i: &str) -> IResult<&str, T> {
+        map_res(digit1, str::parse)(i)
+    }
+
+    fn header(i: &str) -> IResult<&str, usize> {
+        let (i, (_, _, _, id, _, _, _)) = tuple((
+            space0,
+            tag("Monkey"),
+            space1,
+            number,
+            space0,
+            char(':'),
+            space0,
+        ))(i)?;
+
+        Ok((i, id))
+    }
+
+    fn items(i: &str) -> IResult<&str, Vec<i64>> {
+        delimited(
+            space0,
+            separated_list0(tuple((space0, char(','), space0)), number),
+            space0,
+        )(i)
+    }
+
+    fn operation(i: &str) -> IResult<&str, &str> {
+        let (i, _) = tuple((space0, tag("new"), space0, char('='), space0))(i)?;
+
+        rest(i)
+    }
+
+    fn test(i: &str) -> IResult<&str, i64> {
+        let (i, (_, _, _, _, _, divisor, _)) = tuple((
+            space0,
+            tag("divisible"),
+            space1,
+            tag("by"),
+            space1,
+            number,
+            space0,
+        ))(i)?;
+
+        Ok((i, divisor))
+    }
+
+    fn throw(i: &str) -> IResult<&str, usize> {
+        let (i, (_, _, _, _, _, _, _, target, _)) = tuple((
+            space0,
+            tag("throw"),
+            space1,
+            tag("to"),
+            space1,
+            tag("monkey"),
+            space1,
+            number,
+            space0,
+        ))(i)?;
+
+        Ok((i, target))
+    }
+
+    fn run<'a, T>(
+        parser: impl FnMut(&'a str) -> IResult<&'a str, T>,
+        i: &'a str,
+        expected: &str,
+    ) -> Result<T> {
+        match all_consuming(parser)(i) {
+            Ok((_, value)) => Ok(value),
+            Err(_) => bail!("expected {}, got '{}'", expected, i.trim()),
+        }
+    }
+
+    pub fn parse_header(i: &str) -> Result<usize> {
+        run(header, i, "'Monkey <id>:'")
+    }
+
+    pub fn parse_items(i: &str) -> Result<Vec<i64>> {
+        run(items, i, "a comma separated list of worry levels")
+    }
+
+    pub fn parse_operation(i: &str) -> Result<&str> {
+        run(operation, i, "'new = <expression>'")
+    }
+
+    pub fn parse_test(i: &str) -> Result<i64> {
+        run(test, i, "'divisible by <number>'")
+    }
+
+    pub fn parse_throw(i: &str) -> Result<usize> {
+        run(throw, i, "'throw to monkey <id>'")
+    }
+}
+
+const FIELDS: [&str; 5] = ["Starting items", "Operation", "Test", "If true", "If false"];
+
+// A monkey's header line and its `label: value` lines, with 1-based line
+// numbers.
+struct Block<'a> {
+    line: usize,
+    id: usize,
+    fields: Vec<(usize, String, &'a str)>,
+}
+
+impl<'a> Block<'a> {
+    fn field(&self, label: &str) -> Result<(usize, &'a str)> {
+        let mut matches = self.fields.iter().filter(|(_, l, _)| l == label);
+        match (matches.next(), matches.next()) {
+            (Some((line, _, value)), None) => Ok((*line, value)),
+            (Some(_), Some((line, _, _))) => {
+                bail!("line {}: Monkey {}: '{}' given twice", line, self.id, label)
+            }
+            (None, _) => bail!(
+                "line {}: Monkey {}: missing '{}'",
+                self.line,
+                self.id,
+                label
+            ),
+        }
+    }
+
+    fn parse_field<T>(
+        &self,
+        label: &str,
+        parse: impl FnOnce(&'a str) -> Result<T>,
+    ) -> Result<(usize, T)> {
+        let (line, value) = self.field(label)?;
+        let value = parse(value).map_err(|e| {
+            anyhow!(
+                "line {}: Monkey {}: malformed '{}': {}",
+                line,
+                self.id,
+                label,
+                e
+            )
+        })?;
+
+        Ok((line, value))
+    }
+
+    fn throw_target(&self, label: &str, defined: &HashMap<usize, usize>) -> Result<usize> {
+        let (line, target) = self.parse_field(label, parser::parse_throw)?;
+        if !defined.contains_key(&target) {
+            bail!(
+                "line {}: Monkey {}: '{}' throws to monkey {}, which doesn't exist",
+                line,
+                self.id,
+                label,
+                target
+            );
+        }
+
+        Ok(target)
+    }
+
+    fn into_monkey(self, defined: &HashMap<usize, usize>) -> Result<Monkey> {
+        if let Some((line, label, _)) = self
+            .fields
+            .iter()
+            .find(|(_, label, _)| !FIELDS.contains(&label.as_str()))
+        {
+            bail!(
+                "line {}: Monkey {}: unknown field '{}'",
+                line,
+                self.id,
+                label
+            );
+        }
+
+        let (_, items) = self.parse_field("Starting items", parser::parse_items)?;
+        let (_, operation) =
+            self.parse_field("Operation", |i| parser::parse_operation(i)?.parse::<Expr>())?;
+        let (line, test_divisor) = self.parse_field("Test", parser::parse_test)?;
+        if test_divisor == 0 {
+            bail!(
+                "line {}: Monkey {}: can't test divisibility by 0",
+                line,
+                self.id
+            );
+        }
+        let if_true = self.throw_target("If true", defined)?;
+        let if_false = self.throw_target("If false", defined)?;
+
+        Ok(Monkey {
+            id: self.id,
+            items: items.into_iter().map(Item::new).collect(),
+            operation,
+            test_divisor,
+            if_true,
+            if_false,
+            inspect_count: 0,
+        })
+    }
+}
+
+// Parses monkey definitions. Blank lines and indentation don't matter, and
+// fields may come in any order. Errors name the line, the monkey and the
+// field that couldn't be read.
+pub fn parse(lines: &[String]) -> Result<Vec<Monkey>> {
+    let mut blocks: Vec<Block> = vec![];
+
+    for (idx, text) in lines.iter().enumerate() {
+        let line = idx + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        if text.trim_start().starts_with("Monkey") {
+            let id = parser::parse_header(text).map_err(|e| anyhow!("line {}: {}", line, e))?;
+            blocks.push(Block {
+                line,
+                id,
+                fields: vec![],
+            });
+            continue;
+        }
+
+        let Some(block) = blocks.last_mut() else {
+            bail!(
+                "line {}: expected 'Monkey <id>:', got '{}'",
+                line,
+                text.trim()
+            );
+        };
+        let (label, value) = text.split_once(':').ok_or_else(|| {
+            anyhow!(
+                "line {}: Monkey {}: expected '<field>: <value>', got '{}'",
+                line,
+                block.id,
+                text.trim()
+            )
+        })?;
+        let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+        block.fields.push((line, label, value));
+    }
+
+    let mut defined = HashMap::new();
+    for block in blocks.iter() {
+        if let Some(first) = defined.insert(block.id, block.line) {
+            bail!(
+                "line {}: Monkey {} was already defined on line {}",
+                block.line,
+                block.id,
+                first
+            );
+        }
+    }
+
+    blocks
+        .into_iter()
+        .map(|block| block.into_monkey(&defined))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group_modulus, parse, BinOp, Expr, Item, Monkey, Relief, Simulation};
+
+    const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1";
+
+    fn example(relief: Relief) -> Simulation {
+        let lines = EXAMPLE.lines().map(str::to_owned).collect::<Vec<_>>();
+        Simulation::new(parse(&lines).unwrap(), relief).unwrap()
+    }
+
+    fn expr(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    fn binary(op: BinOp, a: Expr, b: Expr) -> Expr {
+        Expr::Binary(op, Box::new(a), Box::new(b))
+    }
+
+    #[test]
+    fn binds_multiplication_tighter_than_addition() {
+        assert_eq!(
+            expr("old + 2 * 3"),
+            binary(
+                BinOp::Add,
+                Expr::Old,
+                binary(BinOp::Mul, Expr::Int(2), Expr::Int(3))
+            )
+        );
+        assert_eq!(expr("1 + 2 * 3").eval(0).unwrap(), 7);
+        assert_eq!(expr("(1 + 2) * 3").eval(0).unwrap(), 9);
+        assert_eq!(expr("old * old + old / 2").eval(10).unwrap(), 105);
+    }
+
+    #[test]
+    fn associates_to_the_left() {
+        assert_eq!(
+            expr("10 - 4 - 3"),
+            binary(
+                BinOp::Sub,
+                binary(BinOp::Sub, Expr::Int(10), Expr::Int(4)),
+                Expr::Int(3)
+            )
+        );
+        assert_eq!(expr("10 - 4 - 3").eval(0).unwrap(), 3);
+        assert_eq!(expr("10 - (4 - 3)").eval(0).unwrap(), 9);
+        assert_eq!(expr("100 / 10 / 5").eval(0).unwrap(), 2);
+    }
+
+    #[test]
+    fn negates_with_unary_minus() {
+        assert_eq!(expr("-old"), binary(BinOp::Sub, Expr::Int(0), Expr::Old));
+        assert_eq!(expr("-old * 2").eval(3).unwrap(), -6);
+        assert_eq!(expr("2 * -3").eval(0).unwrap(), -6);
+        assert_eq!(expr("--old").eval(4).unwrap(), 4);
+        assert_eq!(expr("-(old + 1)").eval(4).unwrap(), -5);
+    }
+
+    #[test]
+    fn displays_expressions_that_parse_back() {
+        for (source, shown) in [
+            ("old * 19", "old * 19"),
+            ("old+6", "old + 6"),
+            ("(old * old)", "old * old"),
+            ("(old + 1) * 2", "(old + 1) * 2"),
+            ("old - (2 - 3)", "old - (2 - 3)"),
+            ("(old - 2) - 3", "old - 2 - 3"),
+            ("old / (2 * 3)", "old / (2 * 3)"),
+            ("old + (2 + 3)", "old + (2 + 3)"),
+            ("-old * 2", "(0 - old) * 2"),
+        ] {
+            let parsed = expr(source);
+            assert_eq!(parsed.to_string(), shown);
+            assert_eq!(expr(shown), parsed);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for (source, message) in [
+            ("old +", "Expected a value in 'old +'"),
+            ("(old + 1", "Missing ')' in '(old + 1'"),
+            ("old 1", "Unexpected trailing input in 'old 1'"),
+            ("new * 2", "Unknown name 'new' in 'new * 2'"),
+            ("old % 2", "Unexpected '%' in 'old % 2'"),
+        ] {
+            assert_eq!(source.parse::<Expr>().unwrap_err().to_string(), message);
+        }
+        assert!(expr("old / 0").eval(1).is_err());
+        assert!(expr("old * old").eval(i64::MAX).is_err());
+    }
+
+    // Monkey 0 always throws to monkey 1, which always throws back, so the
+    // one item goes 0 -> 1 -> 0 every round.
+    fn ping_pong() -> Simulation {
+        let monkey = |id, target| Monkey {
+            id,
+            items: vec![],
+            operation: expr("old + 1"),
+            test_divisor: 1,
+            if_true: target,
+            if_false: target,
+            inspect_count: 0,
+        };
+        let mut first = monkey(0, 1);
+        first.items.push(Item::new(1));
+
+        Simulation::new(vec![monkey(1, 0), first], Relief::DivideBy(1)).unwrap()
+    }
+
+    #[test]
+    fn records_item_lineage() {
+        let mut sim = ping_pong();
+        sim.run(1).unwrap();
+        assert_eq!(sim.lineage(0), None);
+
+        sim.track_lineage();
+        assert_eq!(sim.lineage(0), Some(&[0][..]));
+        sim.run(2).unwrap();
+        assert_eq!(sim.lineage(0), Some(&[0, 1, 0, 1, 0][..]));
+        assert_eq!(sim.lineage(1), None);
+    }
+
+    #[test]
+    fn finds_loops() {
+        let mut sim = ping_pong();
+        sim.track_lineage();
+        sim.run(2).unwrap();
+        assert_eq!(sim.loops(0), vec![&[0, 1][..], &[1, 0], &[0, 1]]);
+        assert_eq!(sim.loop_counts(), vec![(vec![0, 1], 2), (vec![1, 0], 1)]);
+    }
+
+    // Every inspection moves an item one step along its lineage.
+    #[test]
+    fn lineage_matches_inspection_counts() {
+        let mut sim = example(Relief::DivideBy(3));
+        sim.track_lineage();
+        sim.run(20).unwrap();
+
+        let steps = (0..sim.item_count())
+            .map(|item| sim.lineage(item).unwrap().len() - 1)
+            .sum::<usize>();
+        let inspections = sim.monkeys().iter().map(|m| m.inspect_count).sum::<usize>();
+        assert_eq!(steps, inspections);
+        assert_eq!(sim.monkey_business(2), 10605);
+
+        for item in 0..sim.item_count() {
+            let lineage = sim.lineage(item).unwrap();
+            for l in sim.loops(item) {
+                assert!(!l.is_empty() && lineage.windows(l.len()).any(|w| w == l));
+            }
+        }
+    }
+
+    fn modulo_example() -> Simulation {
+        let lines = EXAMPLE.lines().map(str::to_owned).collect::<Vec<_>>();
+        let monkeys = parse(&lines).unwrap();
+        let modulus = group_modulus(&monkeys);
+        Simulation::new(monkeys, Relief::Modulo(modulus)).unwrap()
+    }
+
+    fn counts(sim: &Simulation) -> Vec<usize> {
+        sim.monkeys().iter().map(|m| m.inspect_count).collect()
+    }
+
+    #[test]
+    fn fast_forwards_like_brute_force() {
+        let sim = modulo_example();
+        let mut brute = modulo_example();
+        for round in [0, 1, 20, 333, 1000, 10000] {
+            brute.run(round - brute.rounds_played()).unwrap();
+            assert_eq!(sim.inspection_counts_at(round).unwrap(), counts(&brute));
+        }
+        assert_eq!(sim.monkey_business_at(10000, 2).unwrap(), 2713310158);
+        assert_eq!(brute.monkey_business(2), 2713310158);
+
+        let sim = example(Relief::DivideBy(3));
+        let mut brute = example(Relief::DivideBy(3));
+        brute.run(20).unwrap();
+        assert_eq!(sim.inspection_counts_at(20).unwrap(), counts(&brute));
+        assert_eq!(sim.monkey_business_at(20, 2).unwrap(), 10605);
+    }
+
+    #[test]
+    fn fast_forwards_from_the_middle_of_a_simulation() {
+        let mut sim = modulo_example();
+        let mut brute = modulo_example();
+        sim.run(37).unwrap();
+        assert_eq!(sim.inspection_counts_at(37).unwrap(), counts(&sim));
+
+        for round in [38, 500, 4321] {
+            brute.run(round - brute.rounds_played()).unwrap();
+            assert_eq!(sim.inspection_counts_at(round).unwrap(), counts(&brute));
+        }
+        assert!(sim.inspection_counts_at(36).is_err());
+    }
+
+    #[test]
+    fn finds_item_cycles() {
+        let sim = modulo_example();
+        let bound = group_modulus(sim.monkeys()) as usize * sim.monkeys().len();
+        for item in 0..sim.item_count() {
+            let cycle = sim.item_cycle(item, usize::MAX).unwrap().unwrap();
+            assert!(cycle.start + cycle.length <= bound);
+        }
+        assert!(sim.item_cycle(sim.item_count(), 10).is_err());
+
+        // Without modulo relief the search only goes as far as asked.
+        let sim = example(Relief::Custom(Box::new(|w| w)));
+        assert_eq!(sim.item_cycle(0, 5).unwrap(), None);
+        assert!(sim.inspection_counts_at(1_000_000).is_err());
+    }
+
+    #[test]
+    fn rejects_relief_by_zero() {
+        let lines = EXAMPLE.lines().map(str::to_owned).collect::<Vec<_>>();
+        for relief in [Relief::DivideBy(0), Relief::Modulo(0)] {
+            assert!(Simulation::new(parse(&lines).unwrap(), relief).is_err());
+        }
+    }
+
+    fn parse_error(text: &str) -> String {
+        let lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+        parse(&lines).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_the_example_with_slack() {
+        let text = EXAMPLE.replace("  ", "\t") + "\n\n";
+        let lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+        let monkeys = parse(&lines).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[2].test_divisor, 13);
+        assert_eq!((monkeys[2].if_true, monkeys[2].if_false), (1, 3));
+    }
+
+    #[test]
+    fn reports_a_bad_header() {
+        assert_eq!(
+            parse_error(&EXAMPLE.replace("Monkey 2:", "Monkey two:")),
+            "line 15: expected 'Monkey <id>:', got 'Monkey two:'"
+        );
+        assert_eq!(
+            parse_error(&format!("  Test: divisible by 3\n{EXAMPLE}")),
+            "line 1: expected 'Monkey <id>:', got 'Test: divisible by 3'"
+        );
+    }
+
+    #[test]
+    fn reports_a_missing_field() {
+        assert_eq!(
+            parse_error(&EXAMPLE.replace("  Operation: new = old * 19\n", "")),
+            "line 1: Monkey 0: missing 'Operation'"
+        );
+    }
+
+    #[test]
+    fn reports_a_non_numeric_divisor() {
+        assert_eq!(
+            parse_error(&EXAMPLE.replace("divisible by 19", "divisible by nineteen")),
+            "line 11: Monkey 1: malformed 'Test': \
+             expected 'divisible by <number>', got 'divisible by nineteen'"
+        );
+    }
+
+    #[test]
+    fn reports_an_unknown_throw_target() {
+        assert_eq!(
+            parse_error(
+                &EXAMPLE.replace("If false: throw to monkey 3", "If false: throw to monkey 7")
+            ),
+            "line 6: Monkey 0: 'If false' throws to monkey 7, which doesn't exist"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{bail, Result};
+
+// The block-letter fonts AoC puzzles draw their answers in. The small font is
+// 6 pixels tall (2016 day 8, 2019 days 8 and 11, 2021 day 13, 2022 day 10) and
+// the large one is 10 pixels tall (2018 day 10).
+const SMALL_FONT: &[(char, &str)] = &[
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const LARGE_FONT: &[(char, &str)] = &[
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+fn render(rows: &[Vec<bool>], columns: std::ops::Range<usize>) -> String {
+    rows.iter()
+        .map(|row| {
+            columns
+                .clone()
+                .map(|x| {
+                    if row.get(x).copied().unwrap_or(false) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Font glyphs are compared with their empty edge columns trimmed, the same as
+// glyphs cut out of the screen.
+fn trim_glyph(glyph: &str) -> String {
+    let rows = glyph
+        .lines()
+        .map(|line| line.chars().map(|c| c == '#').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let used = (0..width)
+        .filter(|x| rows.iter().any(|row| row.get(*x).copied().unwrap_or(false)))
+        .collect::<Vec<_>>();
+    match (used.first(), used.last()) {
+        (Some(first), Some(last)) => render(&rows, *first..last + 1),
+        _ => String::new(),
+    }
+}
+
+// Reads block letters from a grid of lit pixels. Blank rows around the text
+// are ignored and letters are split on fully blank columns, so any spacing
+// between letters works.
+pub fn ocr(pixels: &[Vec<bool>]) -> Result<String> {
+    let lit_rows = pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| row.iter().any(|p| *p))
+        .map(|(y, _)| y)
+        .collect::<Vec<_>>();
+    let (Some(top), Some(bottom)) = (lit_rows.first(), lit_rows.last()) else {
+        return Ok(String::new());
+    };
+    let rows = &pixels[*top..=*bottom];
+
+    let font = match rows.len() {
+        6 => SMALL_FONT,
+        10 => LARGE_FONT,
+        height => bail!("No font is {} pixels tall", height),
+    };
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut result = String::new();
+    let mut x = 0;
+    while x < width {
+        let lit = |x: usize| rows.iter().any(|row| row.get(x).copied().unwrap_or(false));
+        if !lit(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && lit(x) {
+            x += 1;
+        }
+
+        let glyph = render(rows, start..x);
+        match font.iter().find(|(_, g)| trim_glyph(g) == glyph) {
+            Some((c, _)) => result.push(*c),
+            None => bail!("Unrecognized glyph at column {}:\n{}", start, glyph),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ocr, LARGE_FONT, SMALL_FONT};
+
+    // Draws `text` the way a puzzle screen would, one blank column between
+    // letters and a blank row above and below.
+    fn draw(font: &[(char, &str)], text: &str) -> Vec<Vec<bool>> {
+        let height = font[0].1.lines().count();
+        let mut pixels = vec![vec![]; height + 2];
+        for c in text.chars() {
+            let (_, glyph) = font.iter().find(|(g, _)| *g == c).unwrap();
+            for (row, line) in pixels[1..].iter_mut().zip(glyph.lines()) {
+                row.extend(line.chars().map(|p| p == '#'));
+                row.push(false);
+            }
+        }
+        let width = pixels[1].len();
+        pixels[0] = vec![false; width];
+        pixels[height + 1] = vec![false; width];
+
+        pixels
+    }
+
+    #[test]
+    fn reads_the_small_font() {
+        let text = SMALL_FONT.iter().map(|(c, _)| *c).collect::<String>();
+        assert_eq!(ocr(&draw(SMALL_FONT, &text)).unwrap(), text);
+        assert_eq!(ocr(&draw(SMALL_FONT, "PZGPKPEB")).unwrap(), "PZGPKPEB");
+    }
+
+    #[test]
+    fn reads_the_large_font() {
+        let text = LARGE_FONT.iter().map(|(c, _)| *c).collect::<String>();
+        assert_eq!(ocr(&draw(LARGE_FONT, &text)).unwrap(), text);
+        assert_eq!(ocr(&draw(LARGE_FONT, "HJZZLXNR")).unwrap(), "HJZZLXNR");
+    }
+
+    fn parse(screen: &str) -> Vec<Vec<bool>> {
+        screen
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    // 2022 day 10 screens, written out by hand rather than built from the
+    // font tables, so a wrong glyph in a table can't match itself.
+    #[test]
+    fn reads_a_real_screen() {
+        let screen = "\
+####.#..#.####.####.####.#..#..##..####.
+#....#..#....#.#.......#.#..#.#..#....#.
+###..####...#..###....#..####.#......#..
+#....#..#..#...#.....#...#..#.#.....#...
+#....#..#.#....#....#....#..#.#..#.#....
+####.#..#.####.#....####.#..#..##..####.";
+        assert_eq!(ocr(&parse(screen)).unwrap(), "EHZFZHCZ");
+
+        let screen = "\
+###..#....###...##..###...##....##.#..#.
+#..#.#....#..#.#..#.#..#.#..#....#.#.#..
+#..#.#....#..#.#..#.###..#.......#.##...
+###..#....###..####.#..#.#.##....#.#.#..
+#....#....#.#..#..#.#..#.#..#.#..#.#.#..
+#....####.#..#.#..#.###...###..##..#..#.";
+        assert_eq!(ocr(&parse(screen)).unwrap(), "PLRABGJK");
+    }
+
+    #[test]
+    fn rejects_unknown_glyphs() {
+        let mut pixels = draw(SMALL_FONT, "AB");
+        for row in pixels[1..7].iter_mut() {
+            row.extend([true, true, true, true]);
+        }
+        let error = ocr(&pixels).unwrap_err().to_string();
+        assert!(
+            error.starts_with("Unrecognized glyph at column 10:"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_heights() {
+        let pixels = vec![vec![true, false, true]; 3];
+        let error = ocr(&pixels).unwrap_err().to_string();
+        assert_eq!(error, "No font is 3 pixels tall");
+    }
+}
@@ -0,0 +1,355 @@
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    geom::{Direction, Point2, Vec2},
+    grid::{Grid, SparseGrid},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Motion {
+    pub step: Vec2,
+    pub count: usize,
+}
+
+// A head step is one direction, or two of them for a diagonal, e.g. `UL`.
+fn parse_step(s: &str) -> Result<Vec2> {
+    let step = s
+        .chars()
+        .map(|c| Direction::try_from(c).map(Vec2::from))
+        .sum::<Result<Vec2>>()?;
+    if s.chars().count() > 2
+        || step.chebyshev() != 1
+        || step.manhattan() != s.chars().count() as isize
+    {
+        bail!("unknown direction '{}'", s);
+    }
+
+    Ok(step)
+}
+
+impl FromStr for Motion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (direction, count) = s
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("expected '<direction> <count>', got '{}'", s))?;
+
+        Ok(Self {
+            step: parse_step(direction)?,
+            count: count
+                .parse()
+                .map_err(|_| anyhow!("'{}' is not a step count", count))?,
+        })
+    }
+}
+
+impl fmt::Display for Motion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.step.y.signum() {
+            -1 => write!(f, "U")?,
+            1 => write!(f, "D")?,
+            _ => {}
+        }
+        match self.step.x.signum() {
+            -1 => write!(f, "L")?,
+            1 => write!(f, "R")?,
+            _ => {}
+        }
+        write!(f, " {}", self.count)
+    }
+}
+
+// Parses one motion per non-blank line.
+pub fn parse_motions(lines: &[String]) -> Result<Vec<Motion>> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| line.parse().map_err(|e| anyhow!("line {}: {}", idx + 1, e)))
+        .collect()
+}
+
+// The area the head covers, starting from the origin. A knot that only ever
+// steps towards the knot ahead of it, as with `Slack`, never leaves it.
+pub fn head_bounds(motions: &[Motion]) -> (Point2, Point2) {
+    let mut head = Point2::ORIGIN;
+    let (mut min, mut max) = (head, head);
+    for motion in motions {
+        head += motion.step * motion.count as isize;
+        min = Point2::new(min.x.min(head.x), min.y.min(head.y));
+        max = Point2::new(max.x.max(head.x), max.y.max(head.y));
+    }
+
+    (min, max)
+}
+
+// What a cell shows when drawing a rope the way the puzzle examples do.
+// A two knot rope draws its tail as `T`, longer ones number every knot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cell {
+    #[default]
+    Empty,
+    Start,
+    Visited,
+    Head,
+    Tail,
+    Knot(usize),
+}
+
+fn render_cell(cell: &Cell) -> char {
+    match cell {
+        Cell::Empty => '.',
+        Cell::Start => 's',
+        Cell::Visited => '#',
+        Cell::Head => 'H',
+        Cell::Tail => 'T',
+        Cell::Knot(n) => char::from_digit(*n as u32, 36).unwrap_or('?'),
+    }
+}
+
+// Where a knot ends up after the knot ahead of it has moved.
+pub trait FollowRule {
+    fn follow(&self, leader: Point2, knot: Point2) -> Point2;
+}
+
+// A knot stays put while it is at most that many steps from its leader,
+// counting diagonal steps, and otherwise closes in one step along each axis
+// at a time. The puzzle's rope is `Slack(1)`.
+pub struct Slack(pub usize);
+
+impl FollowRule for Slack {
+    fn follow(&self, leader: Point2, mut knot: Point2) -> Point2 {
+        while knot.chebyshev(leader).unsigned_abs() > self.0 {
+            knot += (leader - knot).signum();
+        }
+
+        knot
+    }
+}
+
+impl<F: Fn(Point2, Point2) -> Point2> FollowRule for F {
+    fn follow(&self, leader: Point2, knot: Point2) -> Point2 {
+        self(leader, knot)
+    }
+}
+
+// A rope of knots starting on top of each other at the origin. Knot 0 is
+// the head; every knot remembers each cell it has been in.
+pub struct Rope<R: FollowRule> {
+    rule: R,
+    knots: Vec<Point2>,
+    visited: Vec<HashSet<Point2>>,
+}
+
+impl<R: FollowRule> Rope<R> {
+    pub fn new(len: usize, rule: R) -> Result<Self> {
+        if len == 0 {
+            bail!("a rope needs at least one knot");
+        }
+
+        Ok(Self {
+            rule,
+            knots: vec![Point2::ORIGIN; len],
+            visited: vec![HashSet::from([Point2::ORIGIN]); len],
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.knots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.knots.is_empty()
+    }
+
+    pub fn knots(&self) -> &[Point2] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Point2 {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> Point2 {
+        self.knots[self.knots.len() - 1]
+    }
+
+    // Moves the head by one step and lets the rest of the rope catch up.
+    pub fn step(&mut self, step: impl Into<Vec2>) {
+        self.knots[0] += step.into();
+        self.visited[0].insert(self.knots[0]);
+
+        for i in 1..self.knots.len() {
+            self.knots[i] = self.rule.follow(self.knots[i - 1], self.knots[i]);
+            self.visited[i].insert(self.knots[i]);
+        }
+    }
+
+    pub fn apply(&mut self, motion: &Motion) {
+        for _ in 0..motion.count {
+            self.step(motion.step);
+        }
+    }
+
+    pub fn run(&mut self, motions: &[Motion]) {
+        for motion in motions {
+            self.apply(motion);
+        }
+    }
+
+    pub fn visited(&self, knot: usize) -> Option<&HashSet<Point2>> {
+        self.visited.get(knot)
+    }
+
+    pub fn tail_visited(&self) -> &HashSet<Point2> {
+        &self.visited[self.visited.len() - 1]
+    }
+
+    // The rope where it is now, over the starting point. Knots nearer the
+    // head cover the ones behind them.
+    pub fn snapshot(&self) -> SparseGrid<Cell> {
+        let mut grid = SparseGrid::new(Box::new(render_cell));
+        grid.set(Point2::ORIGIN, Cell::Start);
+        for (i, knot) in self.knots.iter().enumerate().rev() {
+            let cell = match i {
+                0 => Cell::Head,
+                _ if self.knots.len() == 2 => Cell::Tail,
+                _ => Cell::Knot(i),
+            };
+            grid.set(*knot, cell);
+        }
+
+        grid
+    }
+
+    // Every cell the knot has been in, with the starting point marked.
+    pub fn visited_grid(&self, knot: usize) -> Option<SparseGrid<Cell>> {
+        let mut grid = SparseGrid::new(Box::new(render_cell));
+        for point in self.visited(knot)? {
+            grid.set(*point, Cell::Visited);
+        }
+        grid.set(Point2::ORIGIN, Cell::Start);
+
+        Some(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{head_bounds, parse_motions, Motion, Rope, Slack};
+    use crate::geom::Point2;
+
+    const EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+    const LARGER_EXAMPLE: &str = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+
+    fn motions(text: &str) -> Vec<Motion> {
+        let lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+        parse_motions(&lines).unwrap()
+    }
+
+    fn points(points: &[(isize, isize)]) -> HashSet<Point2> {
+        points.iter().map(|&(x, y)| Point2::new(x, y)).collect()
+    }
+
+    // The puzzle draws its example on a 6x5 grid with the start in the
+    // bottom left corner, which is exactly the area the head covers.
+    #[test]
+    fn draws_the_puzzle_example() {
+        let motions = motions(EXAMPLE);
+        let window = head_bounds(&motions);
+        let mut rope = Rope::new(2, Slack(1)).unwrap();
+
+        let mut snapshots = vec![];
+        for motion in motions.iter() {
+            rope.apply(motion);
+            snapshots.push(rope.snapshot().render(window).join("\n"));
+        }
+        assert_eq!(snapshots[0], "......\n......\n......\n......\ns..TH.");
+        assert_eq!(snapshots[1], "....H.\n....T.\n......\n......\ns.....");
+        assert_eq!(snapshots[7], "......\n......\n.TH...\n......\ns.....");
+
+        let visited = rope.visited_grid(1).unwrap().render(window).join("\n");
+        assert_eq!(visited, "..##..\n...##.\n.####.\n....#.\ns###..");
+    }
+
+    #[test]
+    fn parses_motions() {
+        let parsed = motions("R 4\n\nUL 2\n  \nD 1\n\n");
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(
+            parsed.iter().map(Motion::to_string).collect::<Vec<_>>(),
+            ["R 4", "UL 2", "D 1"]
+        );
+
+        let lines = ["R 1", "", "UU 2"].map(str::to_owned);
+        assert_eq!(
+            parse_motions(&lines).unwrap_err().to_string(),
+            "line 3: unknown direction 'UU'"
+        );
+        for bad in ["LR 1", "X 1", "URD 1", "R", "R x"] {
+            assert!(bad.parse::<Motion>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn tracks_every_knot() {
+        let mut rope = Rope::new(10, Slack(1)).unwrap();
+        rope.run(&motions(EXAMPLE));
+        assert_eq!(rope.visited(1).unwrap().len(), 13);
+        assert_eq!(rope.tail_visited().len(), 1);
+        assert!(rope.visited(10).is_none());
+
+        let mut rope = Rope::new(10, Slack(1)).unwrap();
+        rope.run(&motions(LARGER_EXAMPLE));
+        assert_eq!(rope.tail_visited().len(), 36);
+        assert_eq!(rope.visited(9), Some(rope.tail_visited()));
+        // Each knot can only go where the one ahead of it has been near.
+        for knot in 1..rope.len() {
+            let ahead = rope.visited(knot - 1).unwrap();
+            assert!(rope
+                .visited(knot)
+                .unwrap()
+                .iter()
+                .all(|p| ahead.iter().any(|q| p.chebyshev(*q) <= 1)));
+        }
+    }
+
+    #[test]
+    fn moves_the_head_diagonally() {
+        let mut rope = Rope::new(3, Slack(1)).unwrap();
+        rope.run(&motions("UR 3\nDL 1"));
+        assert_eq!(
+            rope.knots(),
+            [Point2::new(2, -2), Point2::new(2, -2), Point2::new(1, -1)]
+        );
+        assert_eq!(
+            rope.visited(0),
+            Some(&points(&[(0, 0), (1, -1), (2, -2), (3, -3)]))
+        );
+        assert_eq!(rope.visited(1), Some(&points(&[(0, 0), (1, -1), (2, -2)])));
+        assert_eq!(rope.tail_visited(), &points(&[(0, 0), (1, -1)]));
+    }
+
+    #[test]
+    fn lets_knots_hang_back_with_more_slack() {
+        let mut rope = Rope::new(2, Slack(2)).unwrap();
+        rope.run(&motions("R 5\nU 2\nU 1"));
+        assert_eq!(rope.head(), Point2::new(5, -3));
+        assert_eq!(rope.tail(), Point2::new(4, -1));
+        assert_eq!(
+            rope.tail_visited(),
+            &points(&[(0, 0), (1, 0), (2, 0), (3, 0), (4, -1)])
+        );
+
+        // With no slack at all every knot sits on the head.
+        let mut rope = Rope::new(4, Slack(0)).unwrap();
+        rope.run(&motions(EXAMPLE));
+        assert!(rope.knots().iter().all(|&knot| knot == rope.head()));
+        assert_eq!(rope.tail_visited(), rope.visited(0).unwrap());
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::filesystem::{Directory, Entry, Filesystem};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Cd(String),
+    Ls,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Listing {
+    Dir(String),
+    File(String, usize),
+}
+
+impl Listing {
+    pub fn name(&self) -> &str {
+        match self {
+            Listing::Dir(name) => name,
+            Listing::File(name, _) => name,
+        }
+    }
+}
+
+// A command along with the output lines it produced. Line numbers are
+// 1-based positions in the original transcript.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub line: usize,
+    pub command: Command,
+    pub output: Vec<(usize, Listing)>,
+}
+
+fn parse_command(line: usize, command: &str) -> Result<Command> {
+    if let Some(dir) = command.strip_prefix("cd ") {
+        return Ok(Command::Cd(dir.to_owned()));
+    }
+    if command.trim_end() == "ls" {
+        return Ok(Command::Ls);
+    }
+
+    bail!("line {}: unknown command '{}'", line, command)
+}
+
+fn parse_listing(line: usize, output: &str) -> Result<Listing> {
+    let (first, name) = output
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("line {}: malformed ls output '{}'", line, output))?;
+
+    if first == "dir" {
+        return Ok(Listing::Dir(name.to_owned()));
+    }
+
+    let size = first
+        .parse()
+        .map_err(|_| anyhow!("line {}: bad file size '{}'", line, first))?;
+
+    Ok(Listing::File(name.to_owned(), size))
+}
+
+pub fn parse(lines: &[String]) -> Result<Vec<Session>> {
+    let mut sessions: Vec<Session> = vec![];
+
+    for (idx, text) in lines.iter().enumerate() {
+        let line = idx + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(command) = text.strip_prefix("$ ") {
+            sessions.push(Session {
+                line,
+                command: parse_command(line, command)?,
+                output: vec![],
+            });
+            continue;
+        }
+
+        match sessions.last_mut() {
+            Some(session) if session.command == Command::Ls => {
+                session.output.push((line, parse_listing(line, text)?));
+            }
+            Some(session) => bail!(
+                "line {}: unexpected output after command on line {}",
+                line,
+                session.line
+            ),
+            None => bail!("line {}: output before any command", line),
+        }
+    }
+
+    Ok(sessions)
+}
+
+pub fn replay(sessions: &[Session]) -> Result<Filesystem> {
+    let mut fs = Filesystem::new();
+    let mut listed = HashSet::new();
+
+    for session in sessions {
+        match &session.command {
+            Command::Cd(dir) => fs
+                .cd(dir)
+                .map_err(|e| anyhow!("line {}: {}", session.line, e))?,
+            Command::Ls => {
+                let pwd = fs.current_dir().id();
+                if listed.insert(pwd) {
+                    record_listing(&mut fs, session)?;
+                } else {
+                    check_listing(&fs, session)?;
+                }
+            }
+        }
+    }
+
+    Ok(fs)
+}
+
+pub fn load(lines: &[String]) -> Result<Filesystem> {
+    replay(&parse(lines)?)
+}
+
+// Writes a puzzle-style transcript that rebuilds `fs` when replayed, listing
+// each directory once and moving between them with relative `cd`s.
+pub fn export(fs: &Filesystem) -> Vec<String> {
+    let mut lines = vec!["$ cd /".to_owned()];
+    export_dir(fs, fs.root(), &mut lines);
+
+    lines
+}
+
+fn export_dir(fs: &Filesystem, dir: &Directory, lines: &mut Vec<String>) {
+    let mut entries = fs.ls_dir(dir);
+    entries.sort_by(|a, b| a.name().cmp(b.name()));
+
+    lines.push("$ ls".to_owned());
+    for entry in entries.iter() {
+        lines.push(match entry {
+            Entry::Directory(subdir) => format!("dir {}", subdir.name()),
+            Entry::File(file) => format!("{} {}", file.size(), file.name()),
+        });
+    }
+
+    for entry in entries {
+        if let Entry::Directory(subdir) = entry {
+            lines.push(format!("$ cd {}", subdir.name()));
+            export_dir(fs, subdir, lines);
+            lines.push("$ cd ..".to_owned());
+        }
+    }
+}
+
+fn record_listing(fs: &mut Filesystem, session: &Session) -> Result<()> {
+    let mut seen = HashSet::new();
+    for (line, listing) in session.output.iter() {
+        if !seen.insert(listing.name()) {
+            bail!("line {}: '{}' listed twice", line, listing.name());
+        }
+        match listing {
+            Listing::Dir(name) => fs.add_dir(name.clone()),
+            Listing::File(name, size) => fs.add_file(name.clone(), *size),
+        }
+        .map_err(|e| anyhow!("line {}: {}", line, e))?;
+    }
+
+    Ok(())
+}
+
+fn check_listing(fs: &Filesystem, session: &Session) -> Result<()> {
+    let pwd = fs.current_dir();
+    let path = fs.path_of(pwd);
+    let mut seen = HashSet::new();
+
+    for (line, listing) in session.output.iter() {
+        if !seen.insert(listing.name()) {
+            bail!("line {}: '{}' listed twice", line, listing.name());
+        }
+        match (listing, fs.child(pwd, listing.name())) {
+            (Listing::Dir(_), Some(Entry::Directory(_))) => {}
+            (Listing::File(_, size), Some(Entry::File(file))) if file.size() == *size => {}
+            (Listing::File(name, size), Some(Entry::File(file))) => bail!(
+                "line {}: '{}' in {} was {} bytes, now listed as {}",
+                line,
+                name,
+                path,
+                file.size(),
+                size
+            ),
+            (_, Some(_)) => bail!(
+                "line {}: '{}' in {} changed between file and directory",
+                line,
+                listing.name(),
+                path
+            ),
+            (_, None) => bail!(
+                "line {}: '{}' wasn't in the earlier listing of {}",
+                line,
+                listing.name(),
+                path
+            ),
+        }
+    }
+
+    let entries = fs.ls_dir(pwd).len();
+    if session.output.len() != entries {
+        bail!(
+            "line {}: listing of {} has {} entries, earlier listing had {}",
+            session.line,
+            path,
+            session.output.len(),
+            entries
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{export, load};
+    use crate::filesystem::Filesystem;
+
+    const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_owned).collect()
+    }
+
+    fn load_error(text: &str) -> String {
+        load(&lines(text)).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn accepts_repeated_identical_listings() {
+        let text = format!("{EXAMPLE}\n$ cd /\n$ ls\n8504156 c.dat\ndir d\ndir a\n14848514 b.txt");
+        assert_eq!(load(&lines(&text)).unwrap().dir_sizes()["/"], 48381165);
+    }
+
+    #[test]
+    fn rejects_listings_that_disagree() {
+        let relist = |listing: &str| format!("{EXAMPLE}\n$ cd /a/e\n$ ls\n{listing}");
+        assert_eq!(
+            load_error(&relist("585 i")),
+            "line 26: 'i' in /a/e was 584 bytes, now listed as 585"
+        );
+        assert_eq!(
+            load_error(&relist("dir i")),
+            "line 26: 'i' in /a/e changed between file and directory"
+        );
+        assert_eq!(
+            load_error(&relist("584 i\n1 x")),
+            "line 27: 'x' wasn't in the earlier listing of /a/e"
+        );
+        assert_eq!(
+            load_error(&relist("")),
+            "line 25: listing of /a/e has 0 entries, earlier listing had 1"
+        );
+        assert_eq!(
+            load_error(&relist("584 i\n584 i")),
+            "line 27: 'i' listed twice"
+        );
+    }
+
+    #[test]
+    fn reports_the_offending_line() {
+        assert_eq!(
+            load_error("$ cd /\n$ rm -rf a"),
+            "line 2: unknown command 'rm -rf a'"
+        );
+        assert_eq!(load_error("$ ls\nabc"), "line 2: malformed ls output 'abc'");
+        assert_eq!(load_error("$ ls\nx y"), "line 2: bad file size 'x'");
+        assert_eq!(load_error("dir a"), "line 1: output before any command");
+        assert_eq!(
+            load_error("$ cd /\n\n4 a"),
+            "line 3: unexpected output after command on line 1"
+        );
+        assert_eq!(load_error("$ ls\n1 a\n2 a"), "line 3: 'a' listed twice");
+        assert_eq!(load_error("$ cd /\n$ cd x"), "line 2: No such directory: x");
+    }
+
+    #[test]
+    fn exported_transcripts_replay_to_the_same_sizes() {
+        let fs = load(&lines(EXAMPLE)).unwrap();
+        let replayed = load(&export(&fs)).unwrap();
+        assert_eq!(replayed.dir_sizes(), fs.dir_sizes());
+        assert_eq!(fs.dir_sizes()["/"], 48381165);
+    }
+
+    #[test]
+    fn exports_directories_read_from_disk() {
+        let root = env::temp_dir().join(format!("transcript-test-{}", process::id()));
+        fs::create_dir_all(root.join("a/e")).unwrap();
+        fs::create_dir_all(root.join("d")).unwrap();
+        fs::write(root.join("b.txt"), [0; 100]).unwrap();
+        fs::write(root.join("a/e/i"), [0; 7]).unwrap();
+        fs::write(root.join("d/j"), [0; 20]).unwrap();
+
+        let from_disk = Filesystem::from_disk(&root);
+        fs::remove_dir_all(&root).unwrap();
+        let (from_disk, warnings) = from_disk.unwrap();
+        assert!(warnings.is_empty());
+
+        let replayed = load(&export(&from_disk)).unwrap();
+        assert_eq!(replayed.dir_sizes(), from_disk.dir_sizes());
+        assert_eq!(from_disk.dir_sizes()["/"], 127);
+        assert_eq!(from_disk.dir_sizes()["/a"], 7);
+    }
+}
@@ -0,0 +1,606 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Write,
+};
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registers {
+    values: BTreeMap<String, isize>,
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> isize {
+        self.values.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, name: &str, value: isize) {
+        self.values.insert(name.to_owned(), value);
+    }
+
+    pub fn value(&self, operand: &Operand) -> isize {
+        match operand {
+            Operand::Int(v) => *v,
+            Operand::Register(name) => self.get(name),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, isize)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+}
+
+impl<const N: usize> From<[(&str, isize); N]> for Registers {
+    fn from(values: [(&str, isize); N]) -> Self {
+        Self {
+            values: values.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Int(isize),
+    Register(String),
+}
+
+impl Operand {
+    pub fn register(&self) -> Result<&str> {
+        match self {
+            Operand::Register(name) => Ok(name),
+            Operand::Int(v) => bail!("Expected a register, got {}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: String,
+    pub args: Vec<Operand>,
+    pub line: usize,
+}
+
+// What happens to the program counter once an instruction finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Next,
+    Jump(isize),
+    Halt,
+}
+
+type Execute = Box<dyn Fn(&mut Registers, &[Operand]) -> Result<Effect>>;
+
+pub struct Opcode {
+    pub name: String,
+    pub arity: usize,
+    pub cycles: usize,
+    execute: Execute,
+}
+
+// Operands that aren't integers must name one of the declared registers, so
+// a typo like `addx 12a` is a parse error rather than a read of an empty
+// register.
+#[derive(Default)]
+pub struct InstructionSet {
+    opcodes: HashMap<String, Opcode>,
+    registers: BTreeSet<String>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The handheld device's instruction set from day 10.
+    pub fn handheld() -> Self {
+        let mut set = Self::new();
+        set.add_register("X");
+        set.add("noop", 0, 1, |_, _| Ok(Effect::Next));
+        set.add("addx", 1, 2, |registers, args| {
+            registers.set("X", registers.get("X") + registers.value(&args[0]));
+            Ok(Effect::Next)
+        });
+
+        set
+    }
+
+    pub fn add(
+        &mut self,
+        name: &str,
+        arity: usize,
+        cycles: usize,
+        execute: impl Fn(&mut Registers, &[Operand]) -> Result<Effect> + 'static,
+    ) {
+        self.opcodes.insert(
+            name.to_owned(),
+            Opcode {
+                name: name.to_owned(),
+                arity,
+                cycles,
+                execute: Box::new(execute),
+            },
+        );
+    }
+
+    pub fn add_register(&mut self, name: &str) {
+        self.registers.insert(name.to_owned());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Opcode> {
+        self.opcodes.get(name)
+    }
+
+    pub fn parse_line(&self, line: usize, text: &str) -> Result<Instruction> {
+        let mut tokens = text.split_whitespace();
+        let opcode = tokens
+            .next()
+            .ok_or_else(|| anyhow!("line {}: missing instruction", line))?;
+        let definition = self
+            .get(opcode)
+            .ok_or_else(|| anyhow!("line {}: unknown instruction '{}'", line, opcode))?;
+
+        let args = tokens
+            .map(|token| {
+                let token = token.trim_end_matches(',');
+                match token.parse() {
+                    Ok(v) => Ok(Operand::Int(v)),
+                    Err(_) if self.registers.contains(token) => {
+                        Ok(Operand::Register(token.to_owned()))
+                    }
+                    Err(_) => bail!(
+                        "line {}: '{}' is neither a number nor a register",
+                        line,
+                        token
+                    ),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if args.len() != definition.arity {
+            bail!(
+                "line {}: {} takes {} argument(s), got {}",
+                line,
+                opcode,
+                definition.arity,
+                args.len()
+            );
+        }
+
+        Ok(Instruction {
+            opcode: opcode.to_owned(),
+            args,
+            line,
+        })
+    }
+
+    pub fn parse(&self, lines: &[String]) -> Result<Vec<Instruction>> {
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(idx, text)| self.parse_line(idx + 1, text))
+            .collect()
+    }
+}
+
+// A snapshot of the CPU part way through a cycle, before the instruction
+// being executed has taken effect.
+#[derive(Debug, Clone, Copy)]
+pub struct CycleState<'a> {
+    pub cycle: usize,
+    pub pc: usize,
+    pub registers: &'a Registers,
+    pub instruction: Option<&'a Instruction>,
+}
+
+pub trait Observer {
+    fn during(&mut self, state: &CycleState) -> Result<()>;
+}
+
+impl<F: FnMut(&CycleState)> Observer for F {
+    fn during(&mut self, state: &CycleState) -> Result<()> {
+        self(state);
+        Ok(())
+    }
+}
+
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn during(&mut self, state: &CycleState) -> Result<()> {
+        self.0.during(state)?;
+        self.1.during(state)
+    }
+}
+
+pub enum Breakpoint {
+    Cycle(usize),
+    Pc(usize),
+    Register(String, isize),
+    When(Box<dyn Fn(&CycleState) -> bool>),
+}
+
+impl Breakpoint {
+    pub fn hit(&self, state: &CycleState) -> bool {
+        match self {
+            Breakpoint::Cycle(cycle) => state.cycle == *cycle,
+            Breakpoint::Pc(pc) => state.pc == *pc,
+            Breakpoint::Register(name, value) => state.registers.get(name) == *value,
+            Breakpoint::When(condition) => condition(state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Breakpoint(usize),
+}
+
+// Writes one CSV row per cycle. Register columns are taken from the
+// registers present on the first cycle.
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    columns: Option<Vec<String>>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            columns: None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Observer for TraceWriter<W> {
+    fn during(&mut self, state: &CycleState) -> Result<()> {
+        let columns = match &self.columns {
+            Some(columns) => columns,
+            None => {
+                let columns = state
+                    .registers
+                    .iter()
+                    .map(|(name, _)| name.to_owned())
+                    .collect::<Vec<_>>();
+                writeln!(self.writer, "cycle,pc,instruction,{}", columns.join(","))?;
+                self.columns.insert(columns)
+            }
+        };
+
+        let instruction = state
+            .instruction
+            .map(|i| {
+                let args = i.args.iter().map(|a| match a {
+                    Operand::Int(v) => v.to_string(),
+                    Operand::Register(name) => name.clone(),
+                });
+                std::iter::once(i.opcode.clone())
+                    .chain(args)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+        let values = columns
+            .iter()
+            .map(|name| state.registers.get(name).to_string())
+            .collect::<Vec<_>>();
+        writeln!(
+            self.writer,
+            "{},{},{},{}",
+            state.cycle,
+            state.pc,
+            instruction,
+            values.join(",")
+        )?;
+
+        Ok(())
+    }
+}
+
+pub struct Cpu {
+    pub registers: Registers,
+    pc: usize,
+    current_cycle: usize,
+    instruction_cycles: usize,
+    program: Vec<Instruction>,
+    instruction_set: InstructionSet,
+    breakpoints: Vec<Breakpoint>,
+    paused: bool,
+}
+
+impl Cpu {
+    pub fn new(
+        instruction_set: InstructionSet,
+        program: Vec<Instruction>,
+        registers: Registers,
+    ) -> Self {
+        Self {
+            registers,
+            pc: 0,
+            current_cycle: 1,
+            instruction_cycles: 0,
+            program,
+            instruction_set,
+            breakpoints: vec![],
+            paused: false,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn state(&self) -> CycleState<'_> {
+        CycleState {
+            cycle: self.current_cycle,
+            pc: self.pc,
+            registers: &self.registers,
+            instruction: self.program.get(self.pc),
+        }
+    }
+
+    // Runs until the program ends or a breakpoint is hit, calling `observer`
+    // once during every cycle. A breakpoint stops the CPU before the observer
+    // sees that cycle; calling `run` again resumes from the same cycle.
+    pub fn run(&mut self, observer: &mut impl Observer) -> Result<Stop> {
+        while !self.halted() {
+            let resuming = std::mem::take(&mut self.paused);
+            let state = self.state();
+            if !resuming {
+                if let Some(idx) = self.breakpoints.iter().position(|b| b.hit(&state)) {
+                    self.paused = true;
+                    return Ok(Stop::Breakpoint(idx));
+                }
+            }
+            observer.during(&state)?;
+            self.cycle()?;
+        }
+
+        Ok(Stop::Halted)
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    // The cycle currently in progress, starting from 1. Register values read
+    // between calls to `cycle` are the values "during" this cycle.
+    pub fn current_cycle(&self) -> usize {
+        self.current_cycle
+    }
+
+    pub fn halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    pub fn current_instruction(&self) -> Option<&Instruction> {
+        self.program.get(self.pc)
+    }
+
+    pub fn cycle(&mut self) -> Result<()> {
+        let Some(instruction) = self.program.get(self.pc) else {
+            bail!("CPU is halted");
+        };
+        let opcode = self
+            .instruction_set
+            .get(&instruction.opcode)
+            .ok_or_else(|| anyhow!("line {}: unknown instruction", instruction.line))?;
+
+        if self.instruction_cycles == 0 {
+            self.instruction_cycles = opcode.cycles.max(1);
+        }
+        self.instruction_cycles -= 1;
+        if self.instruction_cycles == 0 {
+            let effect = (opcode.execute)(&mut self.registers, &instruction.args)
+                .map_err(|e| anyhow!("line {}: {}", instruction.line, e))?;
+            self.pc = match effect {
+                Effect::Next => self.pc + 1,
+                Effect::Jump(offset) => {
+                    let target = self.pc as isize + offset;
+                    if target < 0 {
+                        bail!("line {}: jump before start of program", instruction.line);
+                    }
+                    target as usize
+                }
+                Effect::Halt => self.program.len(),
+            };
+        }
+        self.current_cycle += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Breakpoint, Cpu, CycleState, Effect, InstructionSet, Operand, Registers, Stop, TraceWriter,
+    };
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_owned).collect()
+    }
+
+    fn parse_error(text: &str) -> String {
+        InstructionSet::handheld()
+            .parse(&lines(text))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn parses_operands() {
+        let mut set = InstructionSet::handheld();
+        set.add("cpy", 2, 1, |_, _| Ok(Effect::Next));
+        let program = set.parse(&lines("noop\n\naddx -5\ncpy 3, X")).unwrap();
+        assert_eq!(program[1].args, vec![Operand::Int(-5)]);
+        assert_eq!(program[1].line, 3);
+        assert_eq!(
+            program[2].args,
+            vec![Operand::Int(3), Operand::Register("X".into())]
+        );
+    }
+
+    #[test]
+    fn reports_parse_errors_with_line_numbers() {
+        assert_eq!(
+            parse_error("noop\nmul 2"),
+            "line 2: unknown instruction 'mul'"
+        );
+        assert_eq!(
+            parse_error("addx 1 2"),
+            "line 1: addx takes 1 argument(s), got 2"
+        );
+        assert_eq!(
+            parse_error("noop 1"),
+            "line 1: noop takes 0 argument(s), got 1"
+        );
+        assert_eq!(
+            parse_error("noop\n\naddx 12a"),
+            "line 3: '12a' is neither a number nor a register"
+        );
+        assert_eq!(
+            parse_error("addx foo"),
+            "line 1: 'foo' is neither a number nor a register"
+        );
+    }
+
+    #[test]
+    fn runs_the_handheld_example() {
+        let set = InstructionSet::handheld();
+        let program = set.parse(&lines("noop\naddx 3\naddx -5")).unwrap();
+        let mut cpu = Cpu::new(set, program, Registers::from([("X", 1)]));
+
+        let mut during = vec![];
+        cpu.run(&mut |state: &CycleState| during.push(state.registers.get("X")))
+            .unwrap();
+        assert_eq!(during, vec![1, 1, 1, 4, 4]);
+        assert_eq!(cpu.registers.get("X"), -1);
+        assert_eq!(cpu.current_cycle(), 6);
+        assert!(cpu.cycle().is_err());
+    }
+
+    // Multiplies A by B into C with a loop, using registers and a jump the
+    // handheld doesn't have.
+    #[test]
+    fn runs_custom_instructions() {
+        let mut set = InstructionSet::new();
+        for register in ["A", "B", "C"] {
+            set.add_register(register);
+        }
+        set.add("add", 2, 1, |registers, args| {
+            let target = args[0].register()?;
+            registers.set(target, registers.get(target) + registers.value(&args[1]));
+            Ok(Effect::Next)
+        });
+        set.add("jnz", 2, 3, |registers, args| {
+            Ok(match registers.value(&args[0]) {
+                0 => Effect::Next,
+                _ => Effect::Jump(registers.value(&args[1])),
+            })
+        });
+        set.add("hlt", 0, 1, |_, _| Ok(Effect::Halt));
+
+        let program = set
+            .parse(&lines("add C, A\nadd B, -1\njnz B, -2\nhlt\nadd C, 100"))
+            .unwrap();
+        let mut cpu = Cpu::new(set, program, Registers::from([("A", 6), ("B", 7)]));
+        cpu.run(&mut |_: &CycleState| {}).unwrap();
+        assert_eq!(cpu.registers.get("C"), 42);
+        assert_eq!(cpu.current_cycle(), 1 + 7 * 5 + 1);
+    }
+
+    #[test]
+    fn reports_runtime_errors_with_line_numbers() {
+        let run = |text: &str| {
+            let mut set = InstructionSet::new();
+            set.add("inc", 1, 1, |registers, args| {
+                let target = args[0].register()?;
+                registers.set(target, registers.get(target) + 1);
+                Ok(Effect::Next)
+            });
+            set.add("jmp", 1, 1, |registers, args| {
+                Ok(Effect::Jump(registers.value(&args[0])))
+            });
+            let program = set.parse(&lines(text)).unwrap();
+            let mut cpu = Cpu::new(set, program, Registers::new());
+            cpu.run(&mut |_: &CycleState| {}).unwrap_err().to_string()
+        };
+
+        assert_eq!(run("jmp 1\ninc 4"), "line 2: Expected a register, got 4");
+        assert_eq!(run("jmp 1\njmp -5"), "line 2: jump before start of program");
+    }
+
+    fn handheld(text: &str) -> Cpu {
+        let set = InstructionSet::handheld();
+        let program = set.parse(&lines(text)).unwrap();
+        Cpu::new(set, program, Registers::from([("X", 1)]))
+    }
+
+    #[test]
+    fn stops_at_breakpoints_and_resumes() {
+        let mut cpu = handheld("noop\naddx 3\naddx -5\nnoop");
+        let at_cycle = cpu.add_breakpoint(Breakpoint::Cycle(3));
+        let at_pc = cpu.add_breakpoint(Breakpoint::Pc(2));
+        let at_x = cpu.add_breakpoint(Breakpoint::Register("X".into(), -1));
+
+        let mut seen = vec![];
+        let mut record = |state: &CycleState| seen.push(state.cycle);
+        assert_eq!(cpu.run(&mut record).unwrap(), Stop::Breakpoint(at_cycle));
+        assert_eq!((cpu.current_cycle(), cpu.pc()), (3, 1));
+        assert_eq!(cpu.run(&mut record).unwrap(), Stop::Breakpoint(at_pc));
+        assert_eq!((cpu.current_cycle(), cpu.pc()), (4, 2));
+        // `addx` takes two cycles, and the PC stays put during both.
+        assert_eq!(cpu.run(&mut record).unwrap(), Stop::Breakpoint(at_pc));
+        assert_eq!((cpu.current_cycle(), cpu.pc()), (5, 2));
+        assert_eq!(cpu.run(&mut record).unwrap(), Stop::Breakpoint(at_x));
+        assert_eq!(cpu.current_cycle(), 6);
+        assert_eq!(cpu.registers.get("X"), -1);
+        assert_eq!(cpu.run(&mut record).unwrap(), Stop::Halted);
+
+        // Every cycle is observed exactly once, across all the stops.
+        assert_eq!(seen, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn stops_on_custom_conditions() {
+        let mut cpu = handheld("addx 2\naddx 2\naddx 2");
+        cpu.add_breakpoint(Breakpoint::When(Box::new(|state| {
+            state.registers.get("X") > 3
+        })));
+        let mut ignore = |_: &CycleState| {};
+        assert_eq!(cpu.run(&mut ignore).unwrap(), Stop::Breakpoint(0));
+        assert_eq!((cpu.current_cycle(), cpu.registers.get("X")), (5, 5));
+
+        // The condition holds from here on, but resuming always gets past
+        // the cycle the CPU stopped at.
+        assert_eq!(cpu.run(&mut ignore).unwrap(), Stop::Breakpoint(0));
+        assert_eq!(cpu.current_cycle(), 6);
+        cpu.clear_breakpoints();
+        assert_eq!(cpu.run(&mut ignore).unwrap(), Stop::Halted);
+        assert_eq!(cpu.registers.get("X"), 7);
+    }
+
+    #[test]
+    fn writes_a_csv_trace() {
+        let mut cpu = handheld("noop\naddx 3");
+        let mut trace = TraceWriter::new(vec![]);
+        cpu.run(&mut trace).unwrap();
+        assert_eq!(
+            String::from_utf8(trace.into_inner()).unwrap(),
+            "cycle,pc,instruction,X\n1,0,noop,1\n2,1,addx 3,1\n3,1,addx 3,1\n"
+        );
+    }
+}